@@ -0,0 +1,218 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The pipeline of the `kona` command: lex, parse, check, and run.
+
+use std::{fs, path::{Path, PathBuf}, process::ExitCode, rc::Rc};
+
+use clap::ValueEnum;
+use kona_ast::dump::dump_expr;
+use kona_diagnostic::{
+    DiagnosticsEngine,
+    emitter::{Emitter, HumanEmitter, JsonEmitter},
+};
+use kona_eval::{interpreter::Interpreter, prelude};
+use kona_fmt::format_file;
+use kona_parse::{
+    lex::{token_iter::TokenIter, tokenize},
+    parse::parse_file_with_cst,
+};
+use kona_source::{source_file::SourceFile, source_map::SourceMap};
+use kona_syntax::dump::dump_cst;
+use kona_type_ir::{
+    infer::InferContext,
+    tir::dump as tir_dump,
+    ty::context::{Arenas, TyCtxt},
+};
+
+/// A stage of the pipeline, the stages are run in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Lex,
+    Parse,
+    Check,
+    Run,
+}
+
+/// A result of a stage which can be dumped with `--emit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Emit {
+    /// The tokens, one per line.
+    Tokens,
+
+    /// The syntax tree.
+    Ast,
+
+    /// The concrete syntax tree, with the whitespace and comments.
+    Cst,
+
+    /// The typed IR, with the type of every expression.
+    Tir,
+
+    /// The types of the `let` bound variables and the program.
+    Types,
+}
+
+impl Emit {
+    /// The stage that produces the result.
+    pub fn stage(self) -> Stage {
+        match self {
+            Emit::Tokens => Stage::Lex,
+            Emit::Ast | Emit::Cst => Stage::Parse,
+            Emit::Tir | Emit::Types => Stage::Check,
+        }
+    }
+}
+
+/// How diagnostics are printed, selected by `--error-format`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// Human-readable messages with source snippets.
+    Human,
+
+    /// One JSON object per line.
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn emitter(self) -> Box<dyn Emitter> {
+        match self {
+            ErrorFormat::Human => Box::new(HumanEmitter::stderr()),
+            ErrorFormat::Json => Box::new(JsonEmitter::stderr()),
+        }
+    }
+}
+
+/// Runs the pipeline on `path` until the `last` stage, and prints the results
+/// of the stages in `emit` to the standard output.
+///
+/// Diagnostics are printed to the standard error, the pipeline stops at the
+/// first stage with errors and fails.
+pub fn run_pipeline(
+    path: &Path,
+    last: Stage,
+    emit: &[Emit],
+    error_format: ErrorFormat,
+) -> ExitCode {
+    let sm = Rc::new(SourceMap::new());
+    let file = match sm.load_file(path.to_path_buf()) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("error: couldn't read `{}`: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let diag = DiagnosticsEngine::with_emitter(sm.clone(), error_format.emitter());
+
+    if emit.contains(&Emit::Tokens) {
+        print!("{}", dump_tokens(&sm, &file));
+    }
+    if last == Stage::Lex {
+        // Lexes the file again to report the errors in the tokens, the parser
+        // reports them otherwise.
+        let mut tokens = TokenIter::with_diagnostics(&file.src, file.start_pos, &diag);
+        while tokens.eat().is_some() {}
+        return if has_errors(&diag) { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+    }
+
+    let (expr, cst) = parse_file_with_cst(&file, &diag);
+    if has_errors(&diag) {
+        return ExitCode::FAILURE;
+    }
+    if emit.contains(&Emit::Ast) {
+        print!("{}", dump_expr(&expr));
+    }
+    if emit.contains(&Emit::Cst) {
+        print!("{}", dump_cst(&cst));
+    }
+    if last == Stage::Parse {
+        return ExitCode::SUCCESS;
+    }
+
+    let prelude_file = prelude::load_prelude(&sm);
+    let arenas = Arenas::default();
+    let tcx = TyCtxt::new(&arenas);
+    let mut infcx = InferContext::new(&tcx, &diag);
+    let mut interpreter = Interpreter::new();
+    if let Err(err) = prelude::define_prelude(
+        &prelude_file, &diag, &tcx, &mut infcx, &mut interpreter,
+    ) {
+        diag.emit(err.to_diagnostic());
+    }
+    let expr = infcx.infer_program(&expr);
+    if has_errors(&diag) {
+        return ExitCode::FAILURE;
+    }
+    if emit.contains(&Emit::Tir) {
+        print!("{}", tir_dump::dump_expr(expr));
+    }
+    if emit.contains(&Emit::Types) {
+        print!("{}", tir_dump::dump_types(expr));
+    }
+    if last == Stage::Check {
+        return ExitCode::SUCCESS;
+    }
+
+    if let Err(err) = interpreter.eval_program(expr) {
+        diag.emit(err.to_diagnostic());
+    }
+    if has_errors(&diag) {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Formats the files in place, or checks whether they are formatted if `check`
+/// is true. Fails if a file has syntax errors, or is not formatted in the
+/// check mode.
+pub fn format_files(files: &[PathBuf], check: bool, error_format: ErrorFormat) -> ExitCode {
+    let sm = Rc::new(SourceMap::new());
+    let diag = DiagnosticsEngine::with_emitter(sm.clone(), error_format.emitter());
+    let mut success = true;
+    for path in files {
+        let file = match sm.load_file(path.clone()) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("error: couldn't read `{}`: {}", path.display(), err);
+                success = false;
+                continue;
+            }
+        };
+        // The warnings are printed even if the file is formatted.
+        let formatted = format_file(&file, &diag);
+        diag.flush();
+        let Some(formatted) = formatted else {
+            success = false;
+            continue;
+        };
+        if formatted == *file.src {
+            continue;
+        }
+        if check {
+            eprintln!("error: `{}` is not formatted", path.display());
+            success = false;
+        } else if let Err(err) = fs::write(path, formatted) {
+            eprintln!("error: couldn't write `{}`: {}", path.display(), err);
+            success = false;
+        }
+    }
+    if success { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+/// Prints the diagnostics, and returns true if there are errors.
+fn has_errors(diag: &DiagnosticsEngine) -> bool {
+    diag.flush();
+    diag.has_errors()
+}
+
+/// Dumps the tokens of a file, one per line with its position, e.g.
+/// `1:5 Ident "x"`.
+fn dump_tokens(sm: &SourceMap, file: &SourceFile) -> String {
+    tokenize(&file.src, file.start_pos)
+        .map(|token| {
+            let info = sm.lookup_pos_info(token.span.start);
+            let text = sm.lookup_source(token.span.start..token.span.end);
+            format!("{}:{} {:?} {:?}\n", info.line, info.col + 1, token.kind, text)
+        })
+        .collect()
+}
@@ -0,0 +1,463 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The language server, which talks the Language Server Protocol over the
+//! standard input and output.
+//!
+//! The server keeps the text of the open documents, and checks a document from
+//! scratch whenever it changes or a request needs it. Kona programs are single
+//! files, so there is nothing to share between the documents.
+
+use std::{collections::HashMap, error::Error, io, rc::Rc};
+
+use kona_ast::expr::ident::Ident;
+use kona_diagnostic::{
+    DiagnosticsEngine,
+    diagnostic::{Diagnostic, LabelStyle, Level},
+};
+use kona_eval::{interpreter::Interpreter, prelude};
+use kona_parse::parse::parse_file;
+use kona_source::{pos::Pos, source_file::SourceFile, source_map::SourceMap, span::Span};
+use kona_type_ir::{
+    infer::InferContext,
+    tir::expr::{Expr, ExprKind},
+    ty::{context::{Arenas, TyCtxt}, ty::Ty},
+};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    self as lsp, HoverProviderCapability, OneOf, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as _, PublishDiagnostics,
+    },
+    request::{GotoDefinition, HoverRequest, Request as _},
+};
+
+/// Runs the language server until the client shuts it down.
+pub fn run() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server { connection: &connection, documents: HashMap::new() };
+    server.main_loop()?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+
+    /// The text of the open documents.
+    documents: HashMap<Url, String>,
+}
+
+impl Server<'_> {
+    fn main_loop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for message in &self.connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    self.connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => {
+                    self.handle_notification(notification)?;
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => serde_json::from_value(request.params)
+                .map(|params: lsp::HoverParams| {
+                    let params = params.text_document_position_params;
+                    self.with_document(&params.text_document.uri, |text| {
+                        hover(&params.text_document.uri, text, params.position)
+                    })
+                })
+                .map(serde_json::to_value),
+            GotoDefinition::METHOD => serde_json::from_value(request.params)
+                .map(|params: lsp::GotoDefinitionParams| {
+                    let params = params.text_document_position_params;
+                    self.with_document(&params.text_document.uri, |text| {
+                        definition(&params.text_document.uri, text, params.position)
+                    })
+                })
+                .map(serde_json::to_value),
+            method => {
+                return Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unknown request `{}`", method),
+                );
+            }
+        };
+        match result {
+            Ok(Ok(value)) => Response::new_ok(request.id, value),
+            Ok(Err(err)) | Err(err) => {
+                Response::new_err(request.id, ErrorCode::InvalidParams as i32, err.to_string())
+            }
+        }
+    }
+
+    fn handle_notification(
+        &mut self,
+        notification: Notification,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Malformed notifications are ignored, there is no way to reply to
+        // them.
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<lsp::DidOpenTextDocumentParams>(
+                    notification.params,
+                ) {
+                    let document = params.text_document;
+                    self.documents.insert(document.uri.clone(), document.text);
+                    self.publish_diagnostics(document.uri, Some(document.version))?;
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<lsp::DidChangeTextDocumentParams>(
+                    notification.params,
+                ) {
+                    // The documents are synchronized in full, the last change
+                    // is the whole text.
+                    let document = params.text_document;
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        self.documents.insert(document.uri.clone(), change.text);
+                        self.publish_diagnostics(document.uri, Some(document.version))?;
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<lsp::DidCloseTextDocumentParams>(
+                    notification.params,
+                ) {
+                    let uri = params.text_document.uri;
+                    self.documents.remove(&uri);
+                    self.send_notification::<PublishDiagnostics>(
+                        lsp::PublishDiagnosticsParams::new(uri, Vec::new(), None),
+                    )?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn publish_diagnostics(
+        &self,
+        uri: Url,
+        version: Option<i32>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (_, diagnostics) = check(&uri, &self.documents[&uri], |_, _| ());
+        self.send_notification::<PublishDiagnostics>(
+            lsp::PublishDiagnosticsParams::new(uri, diagnostics, version),
+        )
+    }
+
+    fn send_notification<N: lsp::notification::Notification>(
+        &self,
+        params: N::Params,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let notification = Notification::new(N::METHOD.to_string(), params);
+        self.connection.sender.send(Message::Notification(notification))?;
+        Ok(())
+    }
+
+    /// Calls `f` with the text of an open document, or returns `None` if the
+    /// document is not open.
+    fn with_document<R>(&self, uri: &Url, f: impl FnOnce(&str) -> Option<R>) -> Option<R> {
+        self.documents.get(uri).and_then(|text| f(text))
+    }
+}
+
+/// Parses and type checks a document, calls `f` with the typed IR, and
+/// returns its result with the diagnostics of the document.
+///
+/// Like the driver, the type errors are not reported if there are syntax
+/// errors, but the document is type checked anyway so that `f` can inspect
+/// the parts without errors.
+fn check<R>(
+    uri: &Url,
+    text: &str,
+    f: impl for<'tcx> FnOnce(&SourceFile, &'tcx Expr<'tcx>) -> R,
+) -> (R, Vec<lsp::Diagnostic>) {
+    let sm = Rc::new(SourceMap::new());
+    let file = sm.load_virtual_file(uri.to_string(), text.to_string());
+    let prelude_file = prelude::load_prelude(&sm);
+    let diag = DiagnosticsEngine::new(sm.clone());
+
+    let err_count = diag.err_count();
+    let expr = parse_file(&file, &diag);
+    let has_syntax_errors = diag.err_count() > err_count;
+    let mut diagnostics = diag.take_diagnostics();
+
+    let arenas = Arenas::default();
+    let tcx = TyCtxt::new(&arenas);
+    let mut infcx = InferContext::new(&tcx, &diag);
+    let mut interpreter = Interpreter::with_output(Box::new(io::sink()));
+    if let Err(err) = prelude::define_prelude(
+        &prelude_file, &diag, &tcx, &mut infcx, &mut interpreter,
+    ) {
+        diag.emit(err.to_diagnostic());
+    }
+    let expr = infcx.infer_program(&expr);
+    let type_diagnostics = diag.take_diagnostics();
+    if !has_syntax_errors {
+        diagnostics.extend(type_diagnostics);
+    }
+
+    let diagnostics = diagnostics
+        .iter()
+        .filter_map(|diagnostic| to_lsp_diagnostic(uri, &file, diagnostic))
+        .collect();
+    (f(&file, expr), diagnostics)
+}
+
+/// Converts a diagnostic to the protocol, returns `None` if it doesn't point
+/// to the document, e.g. an error in the prelude.
+fn to_lsp_diagnostic(
+    uri: &Url,
+    file: &SourceFile,
+    diagnostic: &Diagnostic,
+) -> Option<lsp::Diagnostic> {
+    let span = diagnostic.primary_span()?;
+    if !file.contains(span.start) {
+        return None;
+    }
+
+    let mut message = diagnostic.message.clone();
+    for label in &diagnostic.labels {
+        if label.style == LabelStyle::Primary && !label.message.is_empty() {
+            message.push_str(&format!("\n{}", label.message));
+        }
+    }
+    for child in &diagnostic.children {
+        message.push_str(&format!("\n{}: {}", child.level, child.message));
+    }
+
+    let related_information = diagnostic.labels
+        .iter()
+        .filter(|label| label.style == LabelStyle::Secondary)
+        .filter(|label| !label.message.is_empty() && file.contains(label.span.start))
+        .map(|label| lsp::DiagnosticRelatedInformation {
+            location: lsp::Location::new(uri.clone(), to_range(file, label.span)),
+            message: label.message.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    Some(lsp::Diagnostic {
+        range: to_range(file, span),
+        severity: Some(match diagnostic.level {
+            Level::Error => lsp::DiagnosticSeverity::ERROR,
+            Level::Warning => lsp::DiagnosticSeverity::WARNING,
+            Level::Note => lsp::DiagnosticSeverity::INFORMATION,
+            Level::Help => lsp::DiagnosticSeverity::HINT,
+        }),
+        code: diagnostic.code.clone().map(lsp::NumberOrString::String),
+        source: Some("kona".to_string()),
+        message,
+        related_information: (!related_information.is_empty()).then_some(related_information),
+        ..lsp::Diagnostic::default()
+    })
+}
+
+/// Shows the type of the expression or the variable at `position`.
+fn hover(uri: &Url, text: &str, position: lsp::Position) -> Option<lsp::Hover> {
+    let (hover, _) = check(uri, text, |file, expr| {
+        let pos = to_pos(file, position);
+        let (span, contents) = match find_node(expr, pos, &mut Vec::new())? {
+            Node::Var(name, ty, _) | Node::Binding(name, ty) => {
+                (name.span, format!("{} : {}", name.name, ty))
+            }
+            Node::Expr(expr) => (expr.span, expr.ty().to_string()),
+        };
+        Some(lsp::Hover {
+            contents: lsp::HoverContents::Markup(lsp::MarkupContent {
+                kind: lsp::MarkupKind::Markdown,
+                value: format!("```kona\n{}\n```", contents),
+            }),
+            range: Some(to_range(file, span)),
+        })
+    });
+    hover
+}
+
+/// Finds where the variable at `position` is declared. The variables in the
+/// prelude have no location.
+fn definition(
+    uri: &Url,
+    text: &str,
+    position: lsp::Position,
+) -> Option<lsp::GotoDefinitionResponse> {
+    let (location, _) = check(uri, text, |file, expr| {
+        let pos = to_pos(file, position);
+        let span = match find_node(expr, pos, &mut Vec::new())? {
+            Node::Var(_, _, binding) => binding?.span,
+            Node::Binding(name, _) => name.span,
+            Node::Expr(_) => return None,
+        };
+        Some(lsp::Location::new(uri.clone(), to_range(file, span)))
+    });
+    location.map(lsp::GotoDefinitionResponse::Scalar)
+}
+
+/// The innermost node of the typed IR at a position.
+enum Node<'tir> {
+    /// A variable, with the identifier that declares it, or `None` if it is
+    /// declared in the prelude.
+    Var(Ident, &'tir Ty<'tir>, Option<Ident>),
+
+    /// The declaration of a variable, `let x` or `fn x`.
+    Binding(Ident, &'tir Ty<'tir>),
+    Expr(&'tir Expr<'tir>),
+}
+
+/// Finds the innermost node containing `pos`, `scope` is the variables in
+/// scope of `expr`, the innermost last.
+///
+/// A span contains its end, so that a cursor right after a name finds it.
+fn find_node<'tir>(
+    expr: &'tir Expr<'tir>,
+    pos: Pos,
+    scope: &mut Vec<Ident>,
+) -> Option<Node<'tir>> {
+    let contains = |span: Span| span.start <= pos && pos <= span.end;
+    if !contains(expr.span) {
+        return None;
+    }
+
+    let node = match &expr.kind {
+        ExprKind::Error | ExprKind::Unit | ExprKind::Lit(_) => None,
+        ExprKind::Var(name) => {
+            let binding = scope.iter().rev().find(|binding| binding.name == name.name);
+            Some(Node::Var(*name, expr.ty(), binding.copied()))
+        }
+        ExprKind::Lambda(param, body) => {
+            match expr.ty().fn_sig() {
+                Some((param_ty, _)) if contains(param.span) => {
+                    Some(Node::Binding(*param, param_ty))
+                }
+                _ => {
+                    scope.push(*param);
+                    let node = find_node(body, pos, scope);
+                    scope.pop();
+                    node
+                }
+            }
+        }
+        ExprKind::Call(func, arg) | ExprKind::InfixCall(_, func, arg) => {
+            find_node(func, pos, scope).or_else(|| find_node(arg, pos, scope))
+        }
+        ExprKind::Let(name, value, body) => {
+            if contains(name.span) {
+                Some(Node::Binding(*name, value.ty()))
+            } else {
+                find_node(value, pos, scope).or_else(|| {
+                    scope.push(*name);
+                    let node = find_node(body, pos, scope);
+                    scope.pop();
+                    node
+                })
+            }
+        }
+        ExprKind::If(cond, then, else_) => find_node(cond, pos, scope)
+            .or_else(|| find_node(then, pos, scope))
+            .or_else(|| find_node(else_, pos, scope)),
+        ExprKind::Block(items) => items.iter().find_map(|item| find_node(item, pos, scope)),
+    };
+    Some(node.unwrap_or(Node::Expr(expr)))
+}
+
+fn to_pos(file: &SourceFile, position: lsp::Position) -> Pos {
+    file.lookup_pos_by_utf16(position.line as usize, position.character as usize)
+}
+
+fn to_position(file: &SourceFile, pos: Pos) -> lsp::Position {
+    let (line, col) = file.lookup_line_and_utf16_col(pos);
+    lsp::Position::new(line as u32, col as u32)
+}
+
+fn to_range(file: &SourceFile, span: Span) -> lsp::Range {
+    lsp::Range::new(to_position(file, span.start), to_position(file, span.end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsp_queries() {
+        let uri = Url::parse("file:///test.kona").unwrap();
+        let text = concat!(
+            "let greeting = \"hi\" in\n",
+            "let twice = fn f => fn x => f (f x) in\n",
+            "twice (fn s => \"🌊\" ^ s ^ greeting) \"\" + 1\n",
+        );
+        let range = |line, start, end| {
+            lsp::Range::new(lsp::Position::new(line, start), lsp::Position::new(line, end))
+        };
+
+        let (_, diagnostics) = check(&uri, text, |_, _| ());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.starts_with("mismatched types"));
+        assert_eq!(diagnostics[0].range.start, lsp::Position::new(2, 0));
+
+        // `🌊` is two UTF-16 code units, `greeting` starts at column 26.
+        let hover = hover(&uri, text, lsp::Position::new(2, 27)).unwrap();
+        assert_eq!(hover.range, Some(range(2, 26, 34)));
+        assert_eq!(
+            hover.contents,
+            lsp::HoverContents::Markup(lsp::MarkupContent {
+                kind: lsp::MarkupKind::Markdown,
+                value: "```kona\ngreeting : string\n```".to_string(),
+            }),
+        );
+
+        let definition_at = |line, character| {
+            match definition(&uri, text, lsp::Position::new(line, character))? {
+                lsp::GotoDefinitionResponse::Scalar(location) => Some(location.range),
+                _ => None,
+            }
+        };
+        assert_eq!(definition_at(2, 27), Some(range(0, 4, 12)));
+        assert_eq!(definition_at(2, 22), Some(range(2, 10, 11)));
+        assert_eq!(definition_at(2, 0), Some(range(1, 4, 9)));
+        assert_eq!(definition_at(2, 20), None);
+    }
+
+    #[test]
+    fn test_lsp_warnings_and_type_errors() {
+        let uri = Url::parse("file:///test.kona").unwrap();
+
+        // The `а` in `pаy` is Cyrillic.
+        let (_, diagnostics) = check(&uri, "let p\u{430}y = 1 in p\u{430}y + true", |_, _| ());
+        let messages = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.lines().next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, [
+            "identifier `p\u{430}y` mixes Latin and Cyrillic characters",
+            "mismatched types",
+        ]);
+
+        // The type errors are not reported after a syntax error.
+        let (_, diagnostics) = check(&uri, "let x = 1 + true in", |_, _| ());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.starts_with("expected expression"), "{:?}", diagnostics);
+    }
+}
@@ -1,35 +1,200 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use std::{env, path::PathBuf};
-
-use kona_source::source_map::SourceMap;
-
-fn main() {
-    // The command line parameters and tasks are simple, we don't need a driver
-    // yet.
-    let args: Vec<String> = env::args().collect();
-
-    match args.as_slice() {
-        [_path] => println!("error: no input file"),
-        [_path, file] => interpret(file),
-        [_path, file, arg] => match arg.as_str() {
-            // cargo run examples/hello.kona --lex
-            "--lex" => lex(file),
-            cmd => println!("error: unknown command '{}'", cmd),
+use std::{io, path::PathBuf, process::ExitCode, thread};
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, error::ErrorKind};
+
+use self::driver::{Emit, ErrorFormat, Stage};
+
+mod driver;
+mod lsp;
+mod repl;
+
+/// The Kona programming language.
+#[derive(Parser)]
+// A command name before any file is parsed as the command, so `kona FILE` and
+// `kona [OPTIONS] <COMMAND>` don't clash, and the global options may come
+// before or after the command.
+#[command(name = "kona", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Runs the file, a shorthand of `kona run <FILE>`. Starts the REPL if
+    /// there is neither a file nor a command.
+    file: Option<PathBuf>,
+
+    /// How diagnostics are printed.
+    #[arg(long, value_enum, global = true, default_value = "human")]
+    error_format: ErrorFormat,
+
+    /// Dumps the results of the pipeline stages, e.g. `--emit=ast,types`.
+    #[arg(long, value_enum, global = true, value_delimiter = ',')]
+    emit: Vec<Emit>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Tokenizes a file, and prints the tokens by default.
+    Lex { file: PathBuf },
+
+    /// Parses a file, and prints the syntax tree by default.
+    Parse { file: PathBuf },
+
+    /// Parses and type checks a file.
+    Check { file: PathBuf },
+
+    /// Type checks and runs a file.
+    Run { file: PathBuf },
+
+    /// Formats files in place.
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Checks whether the files are formatted instead of formatting them,
+        /// fails if any of them is not.
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Starts the read-eval-print loop.
+    Repl,
+
+    /// Starts the language server, which talks over the standard input and
+    /// output.
+    Lsp,
+}
+
+/// The stack size of the thread running the commands. The passes walk the
+/// expressions recursively, as deep as [`MAX_NESTING_DEPTH`] levels.
+///
+/// [`MAX_NESTING_DEPTH`]: kona_parse::parse::MAX_NESTING_DEPTH
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let thread = thread::Builder::new()
+        .name("kona".to_string())
+        .stack_size(STACK_SIZE)
+        .spawn(move || run(cli))
+        .expect("failed to spawn the main thread");
+
+    // A panic is already reported by the panic hook, exit with the same code
+    // as a panic on the main thread.
+    thread.join().unwrap_or(ExitCode::from(101))
+}
+
+fn run(cli: Cli) -> ExitCode {
+    let (file, last, default_emit) = match cli.command {
+        Some(Command::Lex { file }) => (file, Stage::Lex, Some(Emit::Tokens)),
+        Some(Command::Parse { file }) => (file, Stage::Parse, Some(Emit::Ast)),
+        Some(Command::Check { file }) => (file, Stage::Check, None),
+        Some(Command::Run { file }) => (file, Stage::Run, None),
+        Some(Command::Fmt { files, check }) => {
+            if !cli.emit.is_empty() {
+                Cli::command()
+                    .error(ErrorKind::ArgumentConflict, "`--emit` is not supported by `kona fmt`")
+                    .exit();
+            }
+            return driver::format_files(&files, check, cli.error_format);
+        }
+        Some(Command::Repl) => return start_repl(&cli.emit, cli.error_format),
+        Some(Command::Lsp) => return start_lsp(&cli.emit),
+        None => match cli.file {
+            Some(file) => (file, Stage::Run, None),
+            None => return start_repl(&cli.emit, cli.error_format),
+        },
+    };
+
+    let mut emit = cli.emit;
+    if emit.is_empty() {
+        emit.extend(default_emit);
+    }
+    if let Some(late) = emit.iter().find(|emit| emit.stage() > last) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "`--emit={}` needs `kona {}` or a later command",
+                    late.to_possible_value().unwrap().get_name(),
+                    match late.stage() {
+                        Stage::Lex => "lex",
+                        Stage::Parse => "parse",
+                        Stage::Check | Stage::Run => "check",
+                    },
+                ),
+            )
+            .exit();
+    }
+
+    driver::run_pipeline(&file, last, &emit, cli.error_format)
+}
+
+fn start_repl(emit: &[Emit], error_format: ErrorFormat) -> ExitCode {
+    if !emit.is_empty() {
+        Cli::command()
+            .error(ErrorKind::ArgumentConflict, "`--emit` is not supported by the REPL")
+            .exit();
+    }
+    match repl::run(io::stdin().lock(), io::stdout(), error_format.emitter()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
         }
-        _ => println!("{}", "error: wrong arguments"),
     }
 }
 
-fn interpret(file: &str) {
-    println!("{}", file);
+fn start_lsp(emit: &[Emit]) -> ExitCode {
+    if !emit.is_empty() {
+        Cli::command()
+            .error(ErrorKind::ArgumentConflict, "`--emit` is not supported by the language server")
+            .exit();
+    }
+    match lsp::run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
 
-fn lex(file: &str) {
-    let mut sm = SourceMap::new();
-    let sf = sm.load_file(PathBuf::from(file))
-        .expect(&format!("error: failed to load file '{}'", file));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(["kona"].iter().chain(args))
+    }
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+
+        let cli = parse(&["e.kona"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.file, Some(PathBuf::from("e.kona")));
 
-    println!("{:#?}", sf);
+        // The global options go before or after the command.
+        for args in [
+            &["--error-format=json", "check", "e.kona"][..],
+            &["check", "--error-format=json", "e.kona"],
+            &["check", "e.kona", "--error-format", "json"],
+        ] {
+            let cli = parse(args).unwrap();
+            assert!(matches!(cli.command, Some(Command::Check { .. })), "{:?}", args);
+            assert_eq!(cli.error_format, ErrorFormat::Json, "{:?}", args);
+        }
+        let cli = parse(&["--emit=ast,types", "run", "e.kona"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Run { .. })));
+        assert_eq!(cli.emit.len(), 2);
+        let cli = parse(&["--error-format=json", "e.kona"]).unwrap();
+        assert!(cli.command.is_none() && cli.file.is_some());
+
+        assert!(parse(&["check"]).is_err());
+        assert!(parse(&["e.kona", "f.kona"]).is_err());
+    }
 }
@@ -0,0 +1,201 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The read-eval-print loop.
+
+use std::{cell::RefCell, io::{self, BufRead, Write}, rc::Rc};
+
+use kona_ast::decl::Item;
+use kona_diagnostic::{DiagnosticsEngine, emitter::Emitter};
+use kona_eval::{interpreter::Interpreter, prelude};
+use kona_parse::parse::parse_item;
+use kona_source::source_map::SourceMap;
+use kona_type_ir::{infer::InferContext, ty::context::{Arenas, TyCtxt}};
+
+/// Runs the REPL until the end of `input`, the results and the output of the
+/// program are written to `out`.
+///
+/// Every input is a declaration or an expression. The declared variables are
+/// visible to the later inputs. An input can span multiple lines, the REPL
+/// keeps reading while the input is incomplete, e.g. `let x =`, or until an
+/// empty line.
+///
+/// Every input is loaded as a new virtual file `<repl-n>` into the source map
+/// of the session, so diagnostics can point to the earlier inputs.
+pub fn run(
+    mut input: impl BufRead,
+    out: impl Write + 'static,
+    emitter: Box<dyn Emitter>,
+) -> io::Result<()> {
+    let mut out = SharedWriter(Rc::new(RefCell::new(out)));
+    let sm = Rc::new(SourceMap::new());
+    let prelude_file = prelude::load_prelude(&sm);
+    let diag = DiagnosticsEngine::with_emitter(sm.clone(), emitter);
+
+    let arenas = Arenas::default();
+    let tcx = TyCtxt::new(&arenas);
+    let mut infcx = InferContext::new(&tcx, &diag);
+    let mut interpreter = Interpreter::with_output(Box::new(out.clone()));
+    if let Err(err) = prelude::define_prelude(
+        &prelude_file, &diag, &tcx, &mut infcx, &mut interpreter,
+    ) {
+        diag.emit(err.to_diagnostic());
+    }
+    diag.flush();
+
+    let mut count = 0;
+    while let Some(src) = read_input(&mut input, &mut out)? {
+        count += 1;
+        let file = sm.load_virtual_file(format!("<repl-{}>", count), src);
+
+        // The error count of the engine includes the earlier inputs.
+        let err_count = diag.err_count();
+        let has_errors = || {
+            diag.flush();
+            diag.err_count() > err_count
+        };
+
+        let item = parse_item(&file, &diag);
+        if has_errors() {
+            continue;
+        }
+        match item {
+            Item::Decl(decl) => {
+                let decl = infcx.infer_decl(&decl);
+                if has_errors() {
+                    continue;
+                }
+                match interpreter.eval_decl(&decl) {
+                    Ok(value) => {
+                        infcx.define(decl.name.name, decl.scheme);
+                        writeln!(out, "val {} = {} : {}", decl.name.name, value, decl.scheme)?;
+                    }
+                    Err(err) => diag.emit(err.to_diagnostic()),
+                }
+            }
+            Item::Expr(expr) => {
+                let expr = infcx.infer_program(&expr);
+                if has_errors() {
+                    continue;
+                }
+                match interpreter.eval_program(expr) {
+                    Ok(value) => writeln!(out, "{} : {}", value, expr.ty())?,
+                    Err(err) => diag.emit(err.to_diagnostic()),
+                }
+            }
+        }
+        diag.flush();
+    }
+    Ok(())
+}
+
+/// Reads an input, returns `None` at the end of `input`.
+///
+/// The prompt is `- `, and `= ` for the following lines of an incomplete
+/// input, like in Standard ML.
+fn read_input(input: &mut impl BufRead, out: &mut impl Write) -> io::Result<Option<String>> {
+    let mut src = String::new();
+    loop {
+        write!(out, "{}", if src.is_empty() { "- " } else { "= " })?;
+        out.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(out)?;
+            return Ok(if src.is_empty() { None } else { Some(src) });
+        }
+        if line.trim().is_empty() {
+            // An empty line ends an incomplete input, the syntax errors are
+            // reported then.
+            if src.is_empty() {
+                continue;
+            }
+            return Ok(Some(src));
+        }
+        src.push_str(&line);
+        if !is_incomplete(&src) {
+            return Ok(Some(src));
+        }
+    }
+}
+
+/// Returns true if more lines may complete `src`, i.e. there is a syntax error
+/// at the end of it, e.g. "expected expression, found end of file".
+fn is_incomplete(src: &str) -> bool {
+    let sm = SourceMap::new();
+    let file = sm.load_virtual_file("<input>".to_string(), src.to_string());
+    let diag = DiagnosticsEngine::new(Rc::new(sm));
+    parse_item(&file, &diag);
+    diag.take_diagnostics().iter().any(|diagnostic| {
+        diagnostic.primary_span().is_some_and(|span| span.start == file.end_pos)
+    })
+}
+
+/// A writer shared by the REPL and the interpreter, so that the results and
+/// the output of the program are written in order.
+struct SharedWriter<W>(Rc<RefCell<W>>);
+
+impl<W> Clone for SharedWriter<W> {
+    fn clone(&self) -> Self {
+        SharedWriter(self.0.clone())
+    }
+}
+
+impl<W: Write> Write for SharedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kona_diagnostic::emitter::HumanEmitter;
+
+    use super::*;
+
+    /// Runs the REPL with `input`, returns everything written to the output,
+    /// including the diagnostics.
+    fn run_repl(input: &str) -> String {
+        let out = SharedWriter(Rc::new(RefCell::new(Vec::new())));
+        let emitter = HumanEmitter::new(Box::new(out.clone()));
+        run(input.as_bytes(), out.clone(), Box::new(emitter)).unwrap();
+        let bytes = out.0.borrow().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_repl() {
+        let output = run_repl(concat!(
+            "let id = fn x => x\n",
+            "\n",
+            "id 1 + 2\n",
+            "let greeting =\n",
+            "  \"Hello, \" ^\n",
+            "  \"Izumi!\"\n",
+            "println greeting\n",
+            "id true + 1\n",
+            "let x = 1 in x\n",
+        ));
+        assert_eq!(output, [
+            "- val id = <fn> : 'a -> 'a",
+            "- - 3 : int",
+            "- = = val greeting = \"Hello, Izumi!\" : string",
+            "- Hello, Izumi!",
+            "() : unit",
+            "- error: mismatched types",
+            " --> <repl-5>:1:1",
+            "  |",
+            "1 | id true + 1",
+            "  | ^^^^^^^ - the parameter of `+` is `int`",
+            "  | |",
+            "  | expected `int`, found `bool`",
+            "",
+            "- 1 : int",
+            "- \n",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use kona_source::span::Span;
+
+use crate::expr::{Expr, ident::Ident};
+
+/// A top-level declaration `let x = e`, a `let` without `in`. The variable is
+/// visible to the declarations after it.
+pub struct Decl {
+    pub name: Ident,
+    pub value: Expr,
+    pub span: Span,
+}
+
+impl Decl {
+    #[inline]
+    pub fn new(name: Ident, value: Expr, span: Span) -> Decl {
+        Decl { name, value, span }
+    }
+}
+
+/// A top-level item, a declaration or an expression, e.g. an input of the
+/// REPL.
+pub enum Item {
+    Decl(Decl),
+    Expr(Expr),
+}
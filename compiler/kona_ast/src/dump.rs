@@ -0,0 +1,86 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Dumps the AST as an indented tree for debugging, e.g. in
+//! `kona parse --emit=ast`.
+
+use std::fmt::Write;
+
+use crate::expr::{Expr, ExprKind};
+
+/// Dumps an expression, one node per line, the children are indented under
+/// their parent.
+///
+/// ```text
+/// Let x
+///   Lit 1
+///   InfixOp +
+///     Ident x
+///     Lit 1
+/// ```
+pub fn dump_expr(expr: &Expr) -> String {
+    let mut out = String::new();
+    write_expr(&mut out, expr, 0);
+    out
+}
+
+fn write_expr(out: &mut String, expr: &Expr, depth: usize) {
+    let mut children = Vec::new();
+    let node = match &expr.kind {
+        ExprKind::Error => "Error".to_string(),
+        ExprKind::Unit => "Unit".to_string(),
+        ExprKind::Lit(lit) => format!("Lit {}", lit.symbol),
+        ExprKind::Ident(ident) => format!("Ident {}", ident.name),
+        ExprKind::PendingSeq(first, rest) => {
+            children.push(&**first);
+            let mut node = "PendingSeq".to_string();
+            for (op, operand) in rest {
+                write!(node, " {}", op.name).unwrap();
+                children.push(operand);
+            }
+            node
+        }
+        ExprKind::InfixOp(op, lhs, rhs) => {
+            children.extend([&**lhs, &**rhs]);
+            format!("InfixOp {}", op.name)
+        }
+        ExprKind::Infix(fixity, ops, body) => {
+            children.push(&**body);
+            let mut node = format!("Infix {:?} {}", fixity.assoc, fixity.prec);
+            for op in ops {
+                write!(node, " {}", op.name).unwrap();
+            }
+            node
+        }
+        ExprKind::Fn(param, body) => {
+            children.push(&**body);
+            format!("Fn {}", param.name)
+        }
+        ExprKind::FnCall(func, args) => {
+            children.push(&**func);
+            children.extend(args);
+            "FnCall".to_string()
+        }
+        ExprKind::If(cond, then, else_) => {
+            children.extend([&**cond, &**then, &**else_]);
+            "If".to_string()
+        }
+        ExprKind::Paren(inner) => {
+            children.push(&**inner);
+            "Paren".to_string()
+        }
+        ExprKind::Block(items) => {
+            children.extend(items);
+            "Block".to_string()
+        }
+        ExprKind::Let(name, value, body) => {
+            children.extend([&**value, &**body]);
+            format!("Let {}", name.name)
+        }
+    };
+
+    writeln!(out, "{:indent$}{}", "", node, indent = depth * 2).unwrap();
+    for child in children {
+        write_expr(out, child, depth + 1);
+    }
+}
@@ -1,13 +1,30 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
+use kona_memory::intern::symbol::Symbol;
 use kona_source::span::Span;
 
+#[derive(Clone, Copy)]
 pub struct Lit {
     pub kind: LitKind,
+
+    /// The literal as it is written in the source code, including the quotes
+    /// of string literals and the leading `-` of negative numbers.
+    pub symbol: Symbol,
+
+    /// The value of the literal, decoded by the parser.
+    pub value: LitValue,
     pub span: Span,
 }
 
+impl Lit {
+    #[inline]
+    pub fn new(kind: LitKind, symbol: Symbol, value: LitValue, span: Span) -> Lit {
+        Lit { kind, symbol, value, span }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LitKind {
     Int,
     Float,
@@ -15,3 +32,19 @@ pub enum LitKind {
     Char,
     Bool,
 }
+
+/// The value of a literal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LitValue {
+    Int(i64),
+    Float(f64),
+
+    /// The content of a string literal, with the escape sequences replaced.
+    String(Symbol),
+    Char(char),
+    Bool(bool),
+
+    /// The literal is invalid, e.g. an integer out of range, the error is
+    /// reported.
+    Err,
+}
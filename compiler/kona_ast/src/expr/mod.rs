@@ -3,7 +3,7 @@
 
 use kona_source::span::Span;
 
-use self::{lit::Lit, operator::Operator, ident::Ident};
+use self::{lit::Lit, operator::{Fixity, Operator}, ident::Ident};
 
 pub mod ident;
 pub mod operator;
@@ -14,13 +14,46 @@ pub struct Expr {
     pub span: Span,
 }
 
+impl Expr {
+    #[inline]
+    pub fn new(kind: ExprKind, span: Span) -> Expr {
+        Expr { kind, span }
+    }
+}
+
 pub enum ExprKind {
+    /// A placeholder for an erroneous expression. The error has already been
+    /// reported when this is created.
+    Error,
+
+    // Zero-arity tuple `()`, remove this when we have tuples.
+    Unit,
     Lit(Lit),
-    PendingSeq(Vec<Expr>),
+    Ident(Ident),
+
+    /// An infix expression whose operators are not resolved yet, e.g. `a + b *
+    /// c` is `PendingSeq(a, [(+, b), (*, c)])`.
+    ///
+    /// Fixities are declared by the user, so we can't build the tree during
+    /// parsing. The fixity resolution pass rewrites this into nested
+    /// [`ExprKind::InfixOp`]s.
+    PendingSeq(Box<Expr>, Vec<(Operator, Expr)>),
     InfixOp(Operator, Box<Expr>, Box<Expr>),
+
+    /// A fixity declaration `infixl 6 + - in e`, the fixity is only in effect
+    /// in the body `e`.
+    Infix(Fixity, Vec<Operator>, Box<Expr>),
     Fn(Ident, Box<Expr>),
     FnCall(Box<Expr>, Vec<Expr>),
     If(Box<Expr>, Box<Expr>, Box<Expr>),
+
+    /// A parenthesized expression `(e)`, kept in the tree so that later passes
+    /// (and error messages) can see how the user wrote it.
+    Paren(Box<Expr>),
+
+    /// A group expression with at least one `;`, e.g. `(e1; e2)`. The value of
+    /// the block is the value of the last expression. If the group ends with a
+    /// `;`, an empty [`ExprKind::Unit`] is appended at the closing parenthesis.
     Block(Vec<Expr>),
     Let(Ident, Box<Expr>, Box<Expr>),
 }
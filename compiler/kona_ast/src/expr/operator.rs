@@ -29,3 +29,43 @@ impl Operator {
         Operator::with_dummy_span(Symbol::intern(name))
     }
 }
+
+/// The associativity of an infix operator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Assoc {
+    /// Declared with `infixl`, `a + b + c` is `(a + b) + c`.
+    Left,
+
+    /// Declared with `infixr`, `a :: b :: c` is `a :: (b :: c)`.
+    Right,
+
+    /// Declared with `infix`, `a == b == c` is an error.
+    None,
+}
+
+/// The fixity of an infix operator, a pair of precedence and associativity.
+///
+/// Operators with higher precedence bind tighter. The precedence is a digit
+/// from 0 to 9, just like in Standard ML.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fixity {
+    pub assoc: Assoc,
+    pub prec: u8,
+}
+
+impl Fixity {
+    /// The highest precedence.
+    pub const MAX_PREC: u8 = 9;
+
+    #[inline]
+    pub fn new(assoc: Assoc, prec: u8) -> Fixity {
+        Fixity { assoc, prec }
+    }
+}
+
+impl Default for Fixity {
+    /// The fixity of operators without any declaration, `infixl 9`.
+    fn default() -> Fixity {
+        Fixity::new(Assoc::Left, Fixity::MAX_PREC)
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::fmt;
+
+use kona_source::span::Span;
+
+/// A diagnostic message, e.g. an error or a warning, reported by a pass.
+///
+/// A diagnostic is created with a builder-style API, and reported with
+/// [`DiagnosticsEngine::emit`]. It is just a value before that, the pass that
+/// creates it can change it or drop it freely.
+///
+/// ```
+/// # use kona_diagnostic::diagnostic::Diagnostic;
+/// # use kona_source::span::Span;
+/// # let (arg_span, param_span) = (Span::dummy(), Span::dummy());
+/// let diag = Diagnostic::error("mismatched types")
+///     .with_code("E0308")
+///     .with_primary(arg_span, "expected `int`, found `string`")
+///     .with_secondary(param_span, "parameter declared here")
+///     .with_help("try converting the string with `int_of_string`");
+/// ```
+///
+/// [`DiagnosticsEngine::emit`]: crate::DiagnosticsEngine::emit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: Level,
+
+    /// An optional error code, e.g. `E0308`.
+    pub code: Option<String>,
+
+    /// The main message of this diagnostic.
+    pub message: String,
+
+    /// The labeled spans in the source code. Usually there is exactly one
+    /// primary label, pointing to the cause of this diagnostic.
+    pub labels: Vec<Label>,
+
+    /// The notes and helps attached to this diagnostic.
+    pub children: Vec<SubDiagnostic>,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic with the given level and message.
+    pub fn new(level: Level, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            level,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn error(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Level::Error, message)
+    }
+
+    #[inline]
+    pub fn warning(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Level::Warning, message)
+    }
+
+    /// Sets the error code.
+    pub fn with_code(mut self, code: impl Into<String>) -> Diagnostic {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Adds a primary label, the label message can be empty.
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label::new(LabelStyle::Primary, span, message));
+        self
+    }
+
+    /// Adds a secondary label, the label message can be empty.
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label::new(LabelStyle::Secondary, span, message));
+        self
+    }
+
+    /// Attaches a note.
+    pub fn with_note(mut self, message: impl Into<String>) -> Diagnostic {
+        self.children.push(SubDiagnostic::new(Level::Note, message));
+        self
+    }
+
+    /// Attaches a help message.
+    pub fn with_help(mut self, message: impl Into<String>) -> Diagnostic {
+        self.children.push(SubDiagnostic::new(Level::Help, message));
+        self
+    }
+
+    /// Returns the span of the first primary label, or the first label if
+    /// there is no primary one.
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels
+            .iter()
+            .find(|label| label.style == LabelStyle::Primary)
+            .or_else(|| self.labels.first())
+            .map(|label| label.span)
+    }
+
+    #[inline]
+    pub fn is_error(&self) -> bool {
+        self.level == Level::Error
+    }
+}
+
+/// The severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note => "note",
+            Level::Help => "help",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A labeled span in a [`Diagnostic`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    pub style: LabelStyle,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    pub fn new(style: LabelStyle, span: Span, message: impl Into<String>) -> Label {
+        Label { style, span, message: message.into() }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LabelStyle {
+    /// Points to the cause of the diagnostic.
+    Primary,
+
+    /// Points to the related code, which helps to explain the diagnostic.
+    Secondary,
+}
+
+/// A note or help message attached to a [`Diagnostic`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubDiagnostic {
+    pub level: Level,
+    pub message: String,
+}
+
+impl SubDiagnostic {
+    pub fn new(level: Level, message: impl Into<String>) -> SubDiagnostic {
+        SubDiagnostic { level, message: message.into() }
+    }
+}
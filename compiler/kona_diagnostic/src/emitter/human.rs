@@ -0,0 +1,538 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::{collections::BTreeSet, fmt::Write as _, io::{self, Write}, rc::Rc};
+
+use kona_source::{pos::Pos, source_file::SourceFile, source_map::SourceMap};
+
+use crate::diagnostic::{Diagnostic, Label, LabelStyle};
+
+use super::{resolve_span, Emitter};
+
+/// Emits diagnostics in a human-readable format, with the related source code
+/// and underlined labels, similar to rustc:
+///
+/// ```text
+/// error: mismatched types
+///  --> hello.kona:1:9
+///   |
+/// 1 | let x = 1 + "a" in
+///   |         -   ^^^ expected `int`, found `string`
+///   |         |
+///   |         expected due to this
+/// ```
+pub struct HumanEmitter {
+    dst: Box<dyn Write>,
+}
+
+impl HumanEmitter {
+    pub fn new(dst: Box<dyn Write>) -> HumanEmitter {
+        HumanEmitter { dst }
+    }
+
+    /// Creates an emitter that writes to the standard error.
+    pub fn stderr() -> HumanEmitter {
+        HumanEmitter::new(Box::new(io::stderr()))
+    }
+
+    /// Renders a diagnostic to string, with a trailing blank line.
+    pub fn render(diagnostic: &Diagnostic, source_map: &SourceMap) -> String {
+        let mut out = String::new();
+
+        match &diagnostic.code {
+            Some(code) => writeln!(
+                out, "{}[{}]: {}", diagnostic.level, code, diagnostic.message,
+            ),
+            None => writeln!(out, "{}: {}", diagnostic.level, diagnostic.message),
+        }.unwrap();
+
+        let files = group_labels_by_file(diagnostic, source_map);
+        let gutter_width = files
+            .iter()
+            .flat_map(|(_, labels)| labels.iter())
+            .map(|label| (label.end_line + 1).to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter_width);
+
+        for (i, (file, labels)) in files.iter().enumerate() {
+            // Points to the primary label (or the first one) of this file.
+            let first = labels
+                .iter()
+                .find(|label| label.style == LabelStyle::Primary)
+                .unwrap_or(&labels[0]);
+            let (line, col) = file.lookup_line_and_col(first.pos);
+            let arrow = if i == 0 { "-->" } else { ":::" };
+            writeln!(out, "{}{} {}:{}:{}", pad, arrow, file.file_name(), line, col + 1)
+                .unwrap();
+            writeln!(out, "{} |", pad).unwrap();
+
+            render_snippet(&mut out, file, labels, gutter_width);
+        }
+
+        if !diagnostic.children.is_empty() {
+            if !files.is_empty() {
+                writeln!(out, "{} |", pad).unwrap();
+            }
+            for child in &diagnostic.children {
+                writeln!(out, "{} = {}: {}", pad, child.level, child.message).unwrap();
+            }
+        }
+
+        out.push('\n');
+        out
+    }
+}
+
+impl Emitter for HumanEmitter {
+    fn emit(&mut self, diagnostic: &Diagnostic, source_map: &SourceMap) {
+        let rendered = HumanEmitter::render(diagnostic, source_map);
+
+        // There is nowhere else to report a failure of writing diagnostics.
+        let _ = self.dst.write_all(rendered.as_bytes());
+        let _ = self.dst.flush();
+    }
+}
+
+/// The multi-line spans with more lines than this are elided in the middle.
+const MAX_MULTI_LINE_CONTEXT: usize = 4;
+
+/// A label with its position resolved to lines and display columns.
+///
+/// Lines are 0-based line indices. Columns are 0-based display columns, which
+/// take the width of wide characters and tabs into account, see
+/// [`SourceFile::lookup_line_col_and_col_display`].
+struct ResolvedLabel<'a> {
+    style: LabelStyle,
+    message: &'a str,
+    pos: Pos,
+    start_line: usize,
+    start_col: usize,
+    end_line: usize,
+
+    /// The exclusive end column, always greater than `start_col` for a single
+    /// line label, so that an empty span is still underlined with one `^`.
+    end_col: usize,
+}
+
+impl ResolvedLabel<'_> {
+    fn is_multi_line(&self) -> bool {
+        self.start_line != self.end_line
+    }
+
+    fn mark(&self) -> char {
+        match self.style {
+            LabelStyle::Primary => '^',
+            LabelStyle::Secondary => '-',
+        }
+    }
+}
+
+/// Groups the labels by their files, the file of the primary label comes
+/// first, the rest are in the order they first appear.
+fn group_labels_by_file<'a>(
+    diagnostic: &'a Diagnostic,
+    source_map: &SourceMap,
+) -> Vec<(Rc<SourceFile>, Vec<ResolvedLabel<'a>>)> {
+    let mut labels: Vec<&Label> = diagnostic.labels.iter().collect();
+    labels.sort_by_key(|label| label.style != LabelStyle::Primary);
+
+    let mut files: Vec<(Rc<SourceFile>, Vec<ResolvedLabel>)> = Vec::new();
+    for label in labels {
+        let file = source_map.lookup_file(label.span.start);
+        let resolved = resolve_label(&file, label);
+        match files.iter_mut().find(|(f, _)| Rc::ptr_eq(f, &file)) {
+            Some((_, labels)) => labels.push(resolved),
+            None => files.push((file, vec![resolved])),
+        }
+    }
+    files
+}
+
+fn resolve_label<'a>(file: &SourceFile, label: &'a Label) -> ResolvedLabel<'a> {
+    let span = resolve_span(file, label.span);
+    let start_col = display_col(file, span.start);
+
+    let (end_line, end_col) = if span.end <= span.start {
+        // An empty span is still underlined with one `^`.
+        (span.start_line, start_col + 1)
+    } else {
+        let end_col = display_col(file, span.end);
+        if span.end_line == span.start_line {
+            (span.end_line, end_col.max(start_col + 1))
+        } else {
+            (span.end_line, end_col.max(1))
+        }
+    };
+
+    ResolvedLabel {
+        style: label.style,
+        message: &label.message,
+        pos: span.start,
+        start_line: span.start_line,
+        start_col,
+        end_line,
+        end_col,
+    }
+}
+
+/// Returns the display column of `pos`, which must be clamped to its line.
+fn display_col(file: &SourceFile, pos: Pos) -> usize {
+    let (_, _, col_display) = file.lookup_line_col_and_col_display(pos);
+    col_display
+}
+
+/// Returns the source line ready to print, tabs are expanded to four spaces to
+/// match the display columns.
+fn display_line(file: &SourceFile, line_index: usize) -> String {
+    file.line_text(line_index).replace('\t', "    ")
+}
+
+/// Returns true if the label starts before the first non-whitespace character
+/// of its line, such a multi-line label is drawn as `/` in the margin.
+fn starts_at_indentation(file: &SourceFile, label: &ResolvedLabel) -> bool {
+    let text = display_line(file, label.start_line);
+    let indent = text.len() - text.trim_start().len();
+    label.start_col <= indent
+}
+
+fn render_snippet(
+    out: &mut String,
+    file: &SourceFile,
+    labels: &[ResolvedLabel],
+    gutter_width: usize,
+) {
+    let mut multi_line: Vec<&ResolvedLabel> = labels
+        .iter()
+        .filter(|label| label.is_multi_line())
+        .collect();
+    multi_line.sort_by_key(|label| (label.start_line, label.start_col));
+
+    // Each multi-line label takes a track in the margin, the vertical line on
+    // the left side of the source code.
+    let margin_width = if multi_line.is_empty() { 0 } else { multi_line.len() + 1 };
+    let mut active = vec![false; multi_line.len()];
+    let slash: Vec<bool> = multi_line
+        .iter()
+        .map(|label| starts_at_indentation(file, label))
+        .collect();
+
+    let mut lines = BTreeSet::new();
+    for label in labels {
+        lines.insert(label.start_line);
+        lines.insert(label.end_line);
+        if label.end_line - label.start_line <= MAX_MULTI_LINE_CONTEXT {
+            lines.extend(label.start_line..label.end_line);
+        } else {
+            lines.insert(label.start_line + 1);
+            lines.insert(label.end_line - 1);
+        }
+    }
+
+    let write_row = |out: &mut String, gutter: &str, row: Vec<char>| {
+        let row: String = row.into_iter().collect();
+        let line = format!("{:>width$} | {}", gutter, row, width = gutter_width);
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    };
+
+    let margin = |active: &[bool]| -> Vec<char> {
+        let mut row = vec![' '; margin_width];
+        for (track, &is_active) in active.iter().enumerate() {
+            if is_active {
+                row[track] = '|';
+            }
+        }
+        row
+    };
+
+    let mut prev_line = None;
+    for &line in &lines {
+        if matches!(prev_line, Some(prev) if line > prev + 1) {
+            writeln!(out, "...").unwrap();
+        }
+        prev_line = Some(line);
+
+        // The source line.
+        for (track, label) in multi_line.iter().enumerate() {
+            if label.start_line == line && slash[track] {
+                active[track] = true;
+            }
+        }
+        let mut row = margin(&active);
+        for (track, label) in multi_line.iter().enumerate() {
+            if label.start_line == line && slash[track] {
+                row[track] = '/';
+            }
+        }
+        row.extend(display_line(file, line).chars());
+        write_row(out, &(line + 1).to_string(), row);
+
+        // The start of multi-line labels, e.g. ` ____^`.
+        for (track, label) in multi_line.iter().enumerate() {
+            if label.start_line != line || slash[track] {
+                continue;
+            }
+            let mut row = margin(&active);
+            let mark_at = margin_width + label.start_col;
+            row.resize(mark_at + 1, ' ');
+            row[track + 1..mark_at].fill('_');
+            row[mark_at] = label.mark();
+            write_row(out, "", row);
+            active[track] = true;
+        }
+
+        // The single-line labels.
+        let mut single_line: Vec<&ResolvedLabel> = labels
+            .iter()
+            .filter(|label| !label.is_multi_line() && label.start_line == line)
+            .collect();
+        single_line.sort_by_key(|label| (label.start_col, label.end_col));
+        for row in single_line_rows(&margin(&active), margin_width, &single_line) {
+            write_row(out, "", row);
+        }
+
+        // The end of multi-line labels, e.g. `|____^ message`.
+        for (track, label) in multi_line.iter().enumerate() {
+            if label.end_line != line {
+                continue;
+            }
+            let mut row = margin(&active);
+            let mark_at = margin_width + label.end_col - 1;
+            row.resize(mark_at + 1, ' ');
+            row[track + 1..mark_at].fill('_');
+            row[mark_at] = label.mark();
+            if !label.message.is_empty() {
+                row.push(' ');
+                row.extend(label.message.chars());
+            }
+            write_row(out, "", row);
+            active[track] = false;
+        }
+    }
+}
+
+/// Renders the underlines and messages of the single-line labels in one line,
+/// the labels must be sorted by their start columns.
+///
+/// The message of the rightmost label is placed after the underlines, the
+/// other messages are placed below, connected with `|`:
+///
+/// ```text
+/// let x = 1 + "a" in
+///         -   ^^^ expected `int`, found `string`
+///         |
+///         expected due to this
+/// ```
+fn single_line_rows(
+    margin: &[char],
+    margin_width: usize,
+    labels: &[&ResolvedLabel],
+) -> Vec<Vec<char>> {
+    let Some(last) = labels.last() else {
+        return Vec::new();
+    };
+
+    let mut rows = Vec::new();
+
+    let mut underlines = margin.to_vec();
+    for label in labels {
+        let end = margin_width + label.end_col;
+        if underlines.len() < end {
+            underlines.resize(end, ' ');
+        }
+        underlines[margin_width + label.start_col..end].fill(label.mark());
+    }
+    if !last.message.is_empty() {
+        underlines.push(' ');
+        underlines.extend(last.message.chars());
+    }
+    rows.push(underlines);
+
+    let mut pending: Vec<&ResolvedLabel> = labels[..labels.len() - 1]
+        .iter()
+        .filter(|label| !label.message.is_empty())
+        .copied()
+        .collect();
+
+    let connectors = |labels: &[&ResolvedLabel]| -> Vec<char> {
+        let mut row = margin.to_vec();
+        for label in labels {
+            let col = margin_width + label.start_col;
+            if row.len() <= col {
+                row.resize(col + 1, ' ');
+            }
+            row[col] = '|';
+        }
+        row
+    };
+
+    while let Some(label) = pending.pop() {
+        let mut connector_row = connectors(&pending);
+        let col = margin_width + label.start_col;
+        if connector_row.len() <= col {
+            connector_row.resize(col + 1, ' ');
+        }
+        connector_row[col] = '|';
+        rows.push(connector_row);
+
+        let mut message_row = connectors(&pending);
+        message_row.resize(col, ' ');
+        message_row.extend(label.message.chars());
+        rows.push(message_row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use kona_source::span::Span;
+
+    use super::*;
+
+    /// Returns the span of the `n`-th (0-based) occurrence of `pat` in `src`.
+    fn find(file: &SourceFile, pat: &str, n: usize) -> Span {
+        let (offset, _) = file.src.match_indices(pat).nth(n).unwrap();
+        let start = file.start_pos + offset;
+        Span::new(start, start + pat.len())
+    }
+
+    #[test]
+    fn test_render_single_line_labels() {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file(
+            "test.kona".to_string(),
+            "let x = 1 + \"a\" in\nx".to_string(),
+        );
+        let diagnostic = Diagnostic::error("mismatched types")
+            .with_code("E0001")
+            .with_primary(find(&file, "\"a\"", 0), "expected `int`, found `string`")
+            .with_secondary(find(&file, "1", 0), "expected due to this")
+            .with_note("`+` only accepts integers");
+
+        assert_eq!(HumanEmitter::render(&diagnostic, &sm), [
+            "error[E0001]: mismatched types",
+            " --> test.kona:1:13",
+            "  |",
+            "1 | let x = 1 + \"a\" in",
+            "  |         -   ^^^ expected `int`, found `string`",
+            "  |         |",
+            "  |         expected due to this",
+            "  |",
+            "  = note: `+` only accepts integers",
+            "",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_multi_line_labels() {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file(
+            "test.kona".to_string(),
+            "let f = fn x =>\n  x + 1\nin f".to_string(),
+        );
+        let start = find(&file, "fn", 0).start;
+        let end = find(&file, "1", 0).end;
+        let diagnostic = Diagnostic::error("oops")
+            .with_primary(Span::new(start, end), "this function")
+            .with_secondary(find(&file, "f", 2), "");
+
+        assert_eq!(HumanEmitter::render(&diagnostic, &sm), [
+            "error: oops",
+            " --> test.kona:1:9",
+            "  |",
+            "1 |   let f = fn x =>",
+            "  |  _________^",
+            "2 | |   x + 1",
+            "  | |_______^ this function",
+            "3 |   in f",
+            "  |      -",
+            "",
+            "",
+        ].join("\n"));
+
+        // A label starting at the indentation is drawn with a `/`.
+        let start = find(&file, "let", 0).start;
+        let end = find(&file, "f", 2).end;
+        let diagnostic = Diagnostic::error("oops")
+            .with_primary(Span::new(start, end), "");
+
+        assert_eq!(HumanEmitter::render(&diagnostic, &sm), [
+            "error: oops",
+            " --> test.kona:1:1",
+            "  |",
+            "1 | / let f = fn x =>",
+            "2 | |   x + 1",
+            "3 | | in f",
+            "  | |____^",
+            "",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_wide_chars_and_tabs() {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file(
+            "test.kona".to_string(),
+            "\tlet 名前 = \"🌊\" in 名前".to_string(),
+        );
+        let diagnostic = Diagnostic::error("oops")
+            .with_primary(find(&file, "名前", 1), "here")
+            .with_secondary(find(&file, "\"🌊\"", 0), "");
+
+        assert_eq!(HumanEmitter::render(&diagnostic, &sm), [
+            "error: oops",
+            " --> test.kona:1:18",
+            "  |",
+            "1 |     let 名前 = \"🌊\" in 名前",
+            "  |                ----    ^^^^ here",
+            "",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_labels_in_other_files() {
+        let sm = SourceMap::new();
+        let a = sm.load_virtual_file("a.kona".to_string(), "f 1".to_string());
+        let b = sm.load_virtual_file("b.kona".to_string(), "\n\nfn x => x".to_string());
+        let diagnostic = Diagnostic::error("oops")
+            .with_secondary(find(&b, "x", 0), "declared here")
+            .with_primary(find(&a, "1", 0), "");
+
+        assert_eq!(HumanEmitter::render(&diagnostic, &sm), [
+            "error: oops",
+            " --> a.kona:1:3",
+            "  |",
+            "1 | f 1",
+            "  |   ^",
+            " ::: b.kona:3:4",
+            "  |",
+            "3 | fn x => x",
+            "  |    - declared here",
+            "",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_at_end_of_file() {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file("test.kona".to_string(), "(a b\n".to_string());
+        let eof = Span::new(file.end_pos, file.end_pos);
+        let diagnostic = Diagnostic::error("expected `)`, found end of file")
+            .with_primary(eof, "");
+
+        assert_eq!(HumanEmitter::render(&diagnostic, &sm), [
+            "error: expected `)`, found end of file",
+            " --> test.kona:1:5",
+            "  |",
+            "1 | (a b",
+            "  |     ^",
+            "",
+            "",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::{fmt::Write as _, io::{self, Write}};
+
+use kona_source::{pos::Pos, source_map::SourceMap};
+
+use crate::diagnostic::{Diagnostic, Label, LabelStyle};
+
+use super::{resolve_span, Emitter, HumanEmitter};
+
+/// Emits diagnostics as JSON, one object per line, for tools like CI scripts
+/// and editor plugins.
+///
+/// Each diagnostic is written as:
+///
+/// ```text
+/// {
+///   "severity": "error",
+///   "code": "E0001",             // or null
+///   "message": "mismatched types",
+///   "labels": [{
+///     "style": "primary",        // or "secondary"
+///     "message": "expected `int`, found `string`",
+///     "file": "hello.kona",
+///     "byte_start": 12,          // byte offsets in the file, end exclusive
+///     "byte_end": 15,
+///     "line_start": 1,           // 1-based lines and columns, columns are
+///     "column_start": 13,        // counted in characters
+///     "line_end": 1,
+///     "column_end": 16
+///   }],
+///   "children": [{ "severity": "note", "message": "..." }],
+///   "rendered": "error[E0001]: mismatched types\n --> hello.kona:1:13\n..."
+/// }
+/// ```
+///
+/// The positions are the ones shown in `rendered`, the same text the
+/// [`HumanEmitter`] would print. A position at a line break or the end of the
+/// file is right after the last character of the line, so a span ending with
+/// a line break ends before it, both in bytes and in columns.
+pub struct JsonEmitter {
+    dst: Box<dyn Write>,
+}
+
+impl JsonEmitter {
+    pub fn new(dst: Box<dyn Write>) -> JsonEmitter {
+        JsonEmitter { dst }
+    }
+
+    /// Creates an emitter that writes to the standard error.
+    pub fn stderr() -> JsonEmitter {
+        JsonEmitter::new(Box::new(io::stderr()))
+    }
+
+    /// Renders a diagnostic to a single line JSON object, without the line
+    /// break.
+    pub fn render(diagnostic: &Diagnostic, source_map: &SourceMap) -> String {
+        let mut out = String::new();
+
+        out.push_str("{\"severity\":");
+        write_str(&mut out, diagnostic.level.as_str());
+        out.push_str(",\"code\":");
+        match &diagnostic.code {
+            Some(code) => write_str(&mut out, code),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"message\":");
+        write_str(&mut out, &diagnostic.message);
+
+        out.push_str(",\"labels\":[");
+        for (i, label) in diagnostic.labels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_label(&mut out, label, source_map);
+        }
+
+        out.push_str("],\"children\":[");
+        for (i, child) in diagnostic.children.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"severity\":");
+            write_str(&mut out, child.level.as_str());
+            out.push_str(",\"message\":");
+            write_str(&mut out, &child.message);
+            out.push('}');
+        }
+
+        out.push_str("],\"rendered\":");
+        write_str(&mut out, &HumanEmitter::render(diagnostic, source_map));
+        out.push('}');
+        out
+    }
+}
+
+impl Emitter for JsonEmitter {
+    fn emit(&mut self, diagnostic: &Diagnostic, source_map: &SourceMap) {
+        let rendered = JsonEmitter::render(diagnostic, source_map);
+
+        // There is nowhere else to report a failure of writing diagnostics.
+        let _ = writeln!(self.dst, "{}", rendered);
+        let _ = self.dst.flush();
+    }
+}
+
+fn write_label(out: &mut String, label: &Label, source_map: &SourceMap) {
+    let file = source_map.lookup_file(label.span.start);
+    let offset = |pos: Pos| pos.to_usize() - file.start_pos.to_usize();
+    let span = resolve_span(&file, label.span);
+    let (line_start, column_start) = file.lookup_line_and_col(span.start);
+    let (line_end, column_end) = file.lookup_line_and_col(span.end);
+
+    out.push_str("{\"style\":");
+    write_str(out, match label.style {
+        LabelStyle::Primary => "primary",
+        LabelStyle::Secondary => "secondary",
+    });
+    out.push_str(",\"message\":");
+    write_str(out, &label.message);
+    out.push_str(",\"file\":");
+    write_str(out, &file.file_name());
+    write!(
+        out,
+        ",\"byte_start\":{},\"byte_end\":{},\
+         \"line_start\":{},\"column_start\":{},\
+         \"line_end\":{},\"column_end\":{}}}",
+        offset(span.start), offset(span.end),
+        line_start, column_start + 1,
+        line_end, column_end + 1,
+    ).unwrap();
+}
+
+/// Writes `s` as a JSON string literal.
+fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use kona_source::span::Span;
+
+    use super::*;
+
+    #[test]
+    fn test_render_json() {
+        let sm = SourceMap::new();
+        sm.load_virtual_file("a.kona".to_string(), "a".to_string());
+        let file = sm.load_virtual_file(
+            "b.kona".to_string(),
+            "let x =\n  \"名前\" in x".to_string(),
+        );
+        let start = file.start_pos + 10;
+        let diagnostic = Diagnostic::error("mismatched types")
+            .with_code("E0001")
+            .with_primary(Span::new(start, start + 8), "found `string`")
+            .with_help("try `\"1\"`");
+
+        let json = JsonEmitter::render(&diagnostic, &sm);
+        let rendered = HumanEmitter::render(&diagnostic, &sm)
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        assert_eq!(json, [
+            r#"{"severity":"error","code":"E0001","message":"mismatched types","#,
+            r#""labels":[{"style":"primary","message":"found `string`","#,
+            r#""file":"b.kona","byte_start":10,"byte_end":18,"#,
+            r#""line_start":2,"column_start":3,"line_end":2,"column_end":7}],"#,
+            r#""children":[{"severity":"help","message":"try `\"1\"`"}],"#,
+            r#""rendered":""#, &rendered, r#""}"#,
+        ].concat());
+    }
+
+    #[test]
+    fn test_render_json_at_end_of_file() {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file(
+            "test.kona".to_string(),
+            "let x = \"abc in\nx + 1\n".to_string(),
+        );
+        let eof = Span::new(file.end_pos, file.end_pos);
+        let diagnostic = Diagnostic::error("expected `in`, found end of file")
+            .with_primary(eof, "expected `in`");
+
+        // The label points after the last line, as the rendered text does.
+        let json = JsonEmitter::render(&diagnostic, &sm);
+        assert!(json.contains(concat!(
+            r#""byte_start":21,"byte_end":21,"#,
+            r#""line_start":2,"column_start":6,"line_end":2,"column_end":6}"#,
+        )), "{}", json);
+        assert!(json.contains(r#" --> test.kona:2:6\n"#), "{}", json);
+    }
+
+    #[test]
+    fn test_render_json_span_ending_at_line_break() {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file(
+            "test.kona".to_string(),
+            "let x = \"abc in\nx + 1\n".to_string(),
+        );
+        let string = Span::new(file.start_pos + 8, file.start_pos + 16);
+        let diagnostic = Diagnostic::error("unterminated string literal")
+            .with_primary(string, "missing the closing `\"`");
+
+        // The bytes and the columns both stop before the line break.
+        let json = JsonEmitter::render(&diagnostic, &sm);
+        assert!(json.contains(concat!(
+            r#""byte_start":8,"byte_end":15,"#,
+            r#""line_start":1,"column_start":9,"line_end":1,"column_end":16}"#,
+        )), "{}", json);
+    }
+
+    #[test]
+    fn test_escape_str() {
+        let mut out = String::new();
+        write_str(&mut out, "a\"b\\c\nd\u{1}é");
+        assert_eq!(out, r#""a\"b\\c\nd\u0001é""#);
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use kona_source::{pos::Pos, source_file::SourceFile, source_map::SourceMap, span::Span};
+
+use crate::diagnostic::Diagnostic;
+
+pub mod human;
+pub mod json;
+
+pub use human::HumanEmitter;
+pub use json::JsonEmitter;
+
+/// Emitter writes the diagnostics flushed by the [`DiagnosticsEngine`] to
+/// somewhere, e.g. the terminal.
+///
+/// [`DiagnosticsEngine`]: crate::DiagnosticsEngine
+pub trait Emitter {
+    fn emit(&mut self, diagnostic: &Diagnostic, source_map: &SourceMap);
+}
+
+/// A span resolved to the lines in its file. Both emitters report the
+/// positions of a span from this, so they point to the same place.
+pub(crate) struct ResolvedSpan {
+    /// The 0-based line index of the start.
+    pub start_line: usize,
+
+    /// The start of the span, clamped to its line, see [`clamp_to_line`].
+    pub start: Pos,
+
+    /// The 0-based line index of the end.
+    pub end_line: usize,
+
+    /// The end of the span, clamped to its line. It is `start` for an empty
+    /// span.
+    pub end: Pos,
+}
+
+pub(crate) fn resolve_span(file: &SourceFile, span: Span) -> ResolvedSpan {
+    let start_line = file.lookup_line(span.start).unwrap_or(0);
+    let start = clamp_to_line(file, start_line, span.start);
+    if span.end <= span.start {
+        return ResolvedSpan { start_line, start, end_line: start_line, end: start };
+    }
+
+    // The line of the last byte in the span, a span ending with a line break
+    // doesn't extend to the next line.
+    let end_line = file.lookup_line(span.end - 1).unwrap_or(0);
+    let end = clamp_to_line(file, end_line, span.end);
+    ResolvedSpan { start_line, start, end_line, end }
+}
+
+/// Clamps `pos` to the end of the given line, excluding the line break, so a
+/// position at the line break (or the end of file) is shown after the last
+/// character of the line.
+fn clamp_to_line(file: &SourceFile, line_index: usize, pos: Pos) -> Pos {
+    let line_start = file.lookup_line_bounds(line_index).start;
+    pos.min(line_start + file.line_text(line_index).len())
+}
@@ -0,0 +1,132 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::{cell::{Cell, RefCell}, mem, rc::Rc};
+
+use kona_source::{source_map::SourceMap, span::Span};
+
+use crate::{diagnostic::Diagnostic, emitter::{Emitter, HumanEmitter}};
+
+/// Collects the diagnostics reported by all passes.
+///
+/// Diagnostics are buffered instead of printed immediately. The driver decides
+/// when to print them with [`flush`], and passes can check whether there are
+/// errors before going on. How they are printed is decided by the [`Emitter`].
+///
+/// [`flush`]: DiagnosticsEngine::flush
+pub struct DiagnosticsEngine {
+    source_map: Rc<SourceMap>,
+    emitter: RefCell<Box<dyn Emitter>>,
+
+    /// The diagnostics emitted but not flushed yet.
+    diagnostics: RefCell<Vec<Diagnostic>>,
+
+    /// The number of errors reported so far, including the flushed ones.
+    err_count: Cell<usize>,
+}
+
+impl DiagnosticsEngine {
+    /// Creates an engine that prints diagnostics to the standard error with
+    /// the [`HumanEmitter`].
+    pub fn new(source_map: Rc<SourceMap>) -> DiagnosticsEngine {
+        DiagnosticsEngine::with_emitter(source_map, Box::new(HumanEmitter::stderr()))
+    }
+
+    pub fn with_emitter(
+        source_map: Rc<SourceMap>,
+        emitter: Box<dyn Emitter>,
+    ) -> DiagnosticsEngine {
+        DiagnosticsEngine {
+            source_map,
+            emitter: RefCell::new(emitter),
+            diagnostics: RefCell::new(Vec::new()),
+            err_count: Cell::new(0),
+        }
+    }
+
+    pub fn source_map(&self) -> &Rc<SourceMap> {
+        &self.source_map
+    }
+
+    /// Emits a diagnostic into the buffer.
+    pub fn emit(&self, diagnostic: Diagnostic) {
+        if diagnostic.is_error() {
+            self.err_count.set(self.err_count.get() + 1);
+        }
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
+
+    /// Emits an error with a primary span and no label, a shorthand of
+    /// [`emit`](DiagnosticsEngine::emit).
+    pub fn report_err(&self, span: Span, message: String) {
+        self.emit(Diagnostic::error(message).with_primary(span, ""));
+    }
+
+    /// Returns the number of errors reported so far.
+    pub fn err_count(&self) -> usize {
+        self.err_count.get()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.err_count() > 0
+    }
+
+    /// Takes all buffered diagnostics out of the engine, without printing
+    /// them.
+    pub fn take_diagnostics(&self) -> Vec<Diagnostic> {
+        mem::take(&mut *self.diagnostics.borrow_mut())
+    }
+
+    /// Prints and clears all buffered diagnostics.
+    pub fn flush(&self) {
+        let mut emitter = self.emitter.borrow_mut();
+        for diagnostic in self.take_diagnostics() {
+            emitter.emit(&diagnostic, &self.source_map);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kona_source::pos::Pos;
+
+    use crate::diagnostic::Level;
+
+    use super::*;
+
+    fn span(start: usize, end: usize) -> Span {
+        Span::new(Pos::from_usize(start), Pos::from_usize(end))
+    }
+
+    #[test]
+    fn test_emit_is_buffered() {
+        let engine = DiagnosticsEngine::new(Rc::new(SourceMap::new()));
+        engine.emit(Diagnostic::warning("unused variable `x`"));
+        engine.report_err(span(1, 2), "unexpected token".to_string());
+
+        assert_eq!(engine.err_count(), 1);
+
+        let diagnostics = engine.take_diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].level, Level::Warning);
+        assert_eq!(diagnostics[1].primary_span(), Some(span(1, 2)));
+
+        // The error count is kept after the buffer is taken.
+        assert!(engine.take_diagnostics().is_empty());
+        assert!(engine.has_errors());
+    }
+
+    #[test]
+    fn test_primary_span() {
+        let diagnostic = Diagnostic::error("mismatched types")
+            .with_secondary(span(0, 1), "expected due to this")
+            .with_primary(span(4, 6), "expected `int`, found `string`")
+            .with_note("the note");
+        assert_eq!(diagnostic.primary_span(), Some(span(4, 6)));
+
+        let diagnostic = Diagnostic::error("mismatched types")
+            .with_secondary(span(0, 1), "");
+        assert_eq!(diagnostic.primary_span(), Some(span(0, 1)));
+        assert_eq!(Diagnostic::error("no span").primary_span(), None);
+    }
+}
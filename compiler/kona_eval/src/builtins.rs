@@ -0,0 +1,202 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Native implementations of built-in functions.
+
+use std::cmp::Ordering::{self, Equal, Greater, Less};
+
+use crate::value::{NativeFn, Value};
+
+/// A built-in function, its type signature for the type checker and its native
+/// implementation for the interpreter.
+pub struct Builtin {
+    /// The type in Kona's type syntax, e.g. `''a -> ''a -> bool`. The type
+    /// variables are quantified, the ones with two quotes are comparable.
+    pub signature: &'static str,
+    pub native: NativeFn,
+}
+
+/// Defines a built-in function which takes one argument, e.g.
+/// `unary!("intToFloat" : "int -> float", |_, n: Int| ...)`. The first
+/// parameter of the closure is the output.
+macro_rules! unary {
+    (
+        $name:literal : $signature:literal,
+        |$out:pat_param, $a:ident : $a_ty:ident| $body:expr
+    ) => {
+        Builtin {
+            signature: $signature,
+            native: NativeFn {
+                name: $name,
+                arity: 1,
+                func: |$out, args| match args {
+                    [Value::$a_ty($a)] => $body,
+                    _ => unreachable!(concat!("wrong arguments of `", $name, "`")),
+                },
+            },
+        }
+    };
+}
+
+/// Like [`unary!`], but defines a function which takes two arguments.
+macro_rules! binary {
+    (
+        $name:literal : $signature:literal,
+        |$a:ident : $a_ty:ident, $b:ident : $b_ty:ident| $body:expr
+    ) => {
+        Builtin {
+            signature: $signature,
+            native: NativeFn {
+                name: $name,
+                arity: 2,
+                func: |_, args| match args {
+                    [Value::$a_ty($a), Value::$b_ty($b)] => $body,
+                    _ => unreachable!(concat!("wrong arguments of `", $name, "`")),
+                },
+            },
+        }
+    };
+}
+
+/// Defines a polymorphic comparison operator, which is true if the ordering of
+/// its operands matches the pattern. The operands can be of any comparable
+/// type, the type checker rejects functions.
+macro_rules! comparison {
+    ($name:literal, $ordering:pat) => {
+        Builtin {
+            signature: "''a -> ''a -> bool",
+            native: NativeFn {
+                name: $name,
+                arity: 2,
+                func: |_, args| {
+                    let ordering = compare(&args[0], &args[1]);
+                    Ok(Value::Bool(matches!(ordering, $ordering)))
+                },
+            },
+        }
+    };
+}
+
+/// The built-in functions in the prelude.
+pub static BUILTINS: &[Builtin] = &[
+    // Integer arithmetic, overflows and divisions by zero are runtime errors.
+    binary!("+" : "int -> int -> int", |a: Int, b: Int| {
+        int_result(a.checked_add(*b), "add with overflow")
+    }),
+    binary!("-" : "int -> int -> int", |a: Int, b: Int| {
+        int_result(a.checked_sub(*b), "subtract with overflow")
+    }),
+    binary!("*" : "int -> int -> int", |a: Int, b: Int| {
+        int_result(a.checked_mul(*b), "multiply with overflow")
+    }),
+    binary!("/" : "int -> int -> int", |a: Int, b: Int| match b {
+        0 => Err("attempt to divide by zero".to_string()),
+        _ => int_result(a.checked_div(*b), "divide with overflow"),
+    }),
+    binary!("%" : "int -> int -> int", |a: Int, b: Int| match b {
+        0 => Err("attempt to calculate the remainder with a divisor of zero".to_string()),
+        _ => int_result(a.checked_rem(*b), "calculate the remainder with overflow"),
+    }),
+
+    // Float arithmetic, in IEEE 754.
+    binary!("+." : "float -> float -> float", |a: Float, b: Float| Ok(Value::Float(a + b))),
+    binary!("-." : "float -> float -> float", |a: Float, b: Float| Ok(Value::Float(a - b))),
+    binary!("*." : "float -> float -> float", |a: Float, b: Float| Ok(Value::Float(a * b))),
+    binary!("/." : "float -> float -> float", |a: Float, b: Float| Ok(Value::Float(a / b))),
+
+    // Structural comparisons, a NaN is unordered and not equal to itself.
+    comparison!("=", Some(Equal)),
+    comparison!("<>", None | Some(Less | Greater)),
+    comparison!("<", Some(Less)),
+    comparison!(">", Some(Greater)),
+    comparison!("<=", Some(Less | Equal)),
+    comparison!(">=", Some(Greater | Equal)),
+
+    // The interpreter doesn't evaluate the right operand of `&&` and `||` if
+    // the left one decides the result, see `short_circuits`.
+    binary!("&&" : "bool -> bool -> bool", |a: Bool, b: Bool| Ok(Value::Bool(*a && *b))),
+    binary!("||" : "bool -> bool -> bool", |a: Bool, b: Bool| Ok(Value::Bool(*a || *b))),
+
+    binary!("^" : "string -> string -> string", |a: String, b: String| {
+        Ok(Value::String(format!("{}{}", a, b).into()))
+    }),
+
+    unary!("print" : "string -> unit", |out, s: String| {
+        write!(out, "{}", s).map_err(|err| err.to_string())?;
+        Ok(Value::Unit)
+    }),
+    unary!("println" : "string -> unit", |out, s: String| {
+        writeln!(out, "{}", s).map_err(|err| err.to_string())?;
+        Ok(Value::Unit)
+    }),
+
+    // Conversions. The strings are formatted the way the values are written
+    // in Kona, and parsed back the same way.
+    unary!("intToFloat" : "int -> float", |_, n: Int| Ok(Value::Float(*n as f64))),
+    unary!("floatToInt" : "float -> int", |_, x: Float| {
+        // `i64::MAX` can't be represented exactly, it is rounded up to 2^63.
+        if x.is_finite() && *x >= i64::MIN as f64 && *x < i64::MAX as f64 {
+            Ok(Value::Int(*x as i64))
+        } else {
+            Err(format!("cannot convert `{:?}` to `int`", x))
+        }
+    }),
+    unary!("intToString" : "int -> string", |_, n: Int| {
+        Ok(Value::String(n.to_string().into()))
+    }),
+    unary!("floatToString" : "float -> string", |_, x: Float| {
+        Ok(Value::String(format!("{:?}", x).into()))
+    }),
+    unary!("boolToString" : "bool -> string", |_, b: Bool| {
+        Ok(Value::String(b.to_string().into()))
+    }),
+    unary!("charToString" : "char -> string", |_, c: Char| {
+        Ok(Value::String(c.to_string().into()))
+    }),
+    unary!("charToInt" : "char -> int", |_, c: Char| Ok(Value::Int(*c as i64))),
+    unary!("intToChar" : "int -> char", |_, n: Int| {
+        match u32::try_from(*n).ok().and_then(char::from_u32) {
+            Some(c) => Ok(Value::Char(c)),
+            None => Err(format!("`{}` is not a Unicode scalar value", n)),
+        }
+    }),
+    unary!("stringToInt" : "string -> int", |_, s: String| match s.parse() {
+        Ok(n) => Ok(Value::Int(n)),
+        Err(_) => Err(format!("cannot convert {:?} to `int`", s)),
+    }),
+    unary!("stringToFloat" : "string -> float", |_, s: String| match s.parse() {
+        Ok(x) => Ok(Value::Float(x)),
+        Err(_) => Err(format!("cannot convert {:?} to `float`", s)),
+    }),
+];
+
+/// Returns true if `native` is `&&` or `||`, and the left operand `lhs`
+/// decides the result, i.e. `false && _` and `true || _`.
+pub fn short_circuits(native: &NativeFn, lhs: bool) -> bool {
+    match native.name {
+        "&&" => !lhs,
+        "||" => lhs,
+        _ => false,
+    }
+}
+
+fn int_result<'tir>(result: Option<i64>, overflow: &str) -> Result<Value<'tir>, String> {
+    result.map(Value::Int).ok_or_else(|| format!("attempt to {}", overflow))
+}
+
+/// Compares two values of the same comparable type, returns `None` if they
+/// are unordered, i.e. one of them is a NaN.
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Unit, Value::Unit) => Some(Equal),
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+        (Value::Closure(_) | Value::Native(..), _) => {
+            unreachable!("comparing functions, their types are not comparable")
+        }
+        _ => unreachable!("comparing values of different types"),
+    }
+}
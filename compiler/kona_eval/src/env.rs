@@ -0,0 +1,47 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::rc::Rc;
+
+use kona_memory::intern::symbol::Symbol;
+
+use crate::value::Value;
+
+/// The runtime environment, an immutable linked list of bindings.
+///
+/// Extending an environment doesn't change it, so closures can capture the
+/// environment by cloning the head pointer.
+#[derive(Clone, Default)]
+pub struct Env<'tir> {
+    head: Option<Rc<Binding<'tir>>>,
+}
+
+struct Binding<'tir> {
+    name: Symbol,
+    value: Value<'tir>,
+    next: Option<Rc<Binding<'tir>>>,
+}
+
+impl<'tir> Env<'tir> {
+    pub fn new() -> Env<'tir> {
+        Env::default()
+    }
+
+    /// Returns a new environment with `name` bound to `value`, shadowing the
+    /// previous binding of `name`.
+    pub fn extend(&self, name: Symbol, value: Value<'tir>) -> Env<'tir> {
+        let binding = Binding { name, value, next: self.head.clone() };
+        Env { head: Some(Rc::new(binding)) }
+    }
+
+    pub fn lookup(&self, name: Symbol) -> Option<&Value<'tir>> {
+        let mut binding = self.head.as_deref();
+        while let Some(b) = binding {
+            if b.name == name {
+                return Some(&b.value);
+            }
+            binding = b.next.as_deref();
+        }
+        None
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::{io::{self, Write}, rc::Rc};
+
+use kona_ast::expr::lit::{Lit, LitValue};
+use kona_diagnostic::Diagnostic;
+use kona_memory::intern::symbol::Symbol;
+use kona_source::span::Span;
+use kona_type_ir::tir::{decl::Decl, expr::{Expr, ExprKind}};
+
+use crate::{builtins, env::Env, value::{Closure, NativeFn, Value}};
+
+/// An error that stops the evaluation, e.g. a division by zero.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>, span: Span) -> RuntimeError {
+        RuntimeError { message: message.into(), span }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.message.clone()).with_primary(self.span, "")
+    }
+}
+
+/// A tree-walking interpreter of the typed IR.
+///
+/// The program must be well-typed, the interpreter doesn't check types again.
+/// An ill-typed program (e.g. with type errors reported) may panic.
+pub struct Interpreter<'tir> {
+    globals: Env<'tir>,
+    out: Box<dyn Write>,
+}
+
+impl<'tir> Interpreter<'tir> {
+    /// Creates an interpreter which prints to the standard output.
+    pub fn new() -> Interpreter<'tir> {
+        Interpreter::with_output(Box::new(io::stdout()))
+    }
+
+    pub fn with_output(out: Box<dyn Write>) -> Interpreter<'tir> {
+        Interpreter { globals: Env::new(), out }
+    }
+
+    /// Defines a variable visible to the whole program.
+    pub fn define(&mut self, name: Symbol, value: Value<'tir>) {
+        self.globals = self.globals.extend(name, value);
+    }
+
+    /// Defines a native function visible to the whole program.
+    pub fn define_native(&mut self, native: &'static NativeFn) {
+        self.define(Symbol::intern(native.name), Value::Native(native, Rc::new([])));
+    }
+
+    /// Evaluates a program in the global environment.
+    pub fn eval_program(&mut self, expr: &'tir Expr<'tir>) -> Result<Value<'tir>, RuntimeError> {
+        let env = self.globals.clone();
+        let value = self.eval(expr, &env);
+        let _ = self.out.flush();
+        value
+    }
+
+    /// Evaluates a top-level declaration, and defines the variable for the
+    /// rest of the program. Returns the value of the variable.
+    pub fn eval_decl(&mut self, decl: &Decl<'tir>) -> Result<Value<'tir>, RuntimeError> {
+        let value = self.eval_program(decl.value)?;
+        self.define(decl.name.name, value.clone());
+        Ok(value)
+    }
+
+    fn eval(&mut self, expr: &'tir Expr<'tir>, env: &Env<'tir>) -> Result<Value<'tir>, RuntimeError> {
+        match &expr.kind {
+            ExprKind::Error => unreachable!("evaluating an erroneous program"),
+            ExprKind::Unit => Ok(Value::Unit),
+            ExprKind::Lit(lit) => Ok(eval_lit(lit)),
+            ExprKind::Var(ident) => Ok(lookup(env, ident.name)),
+            ExprKind::Lambda(param, body) => {
+                let closure = Closure { param: param.name, body, env: env.clone() };
+                Ok(Value::Closure(Rc::new(closure)))
+            }
+            ExprKind::Call(func, arg) => {
+                let func = self.eval(func, env)?;
+                let arg = self.eval(arg, env)?;
+                self.apply(func, arg, expr.span)
+            }
+            ExprKind::InfixCall(op, lhs, rhs) => {
+                let func = lookup(env, op.name);
+                let lhs = self.eval(lhs, env)?;
+                if let (Value::Native(native, _), Value::Bool(lhs)) = (&func, &lhs) {
+                    if builtins::short_circuits(native, *lhs) {
+                        return Ok(Value::Bool(*lhs));
+                    }
+                }
+                let rhs = self.eval(rhs, env)?;
+                let partial = self.apply(func, lhs, expr.span)?;
+                self.apply(partial, rhs, expr.span)
+            }
+            ExprKind::Let(name, value, body) => {
+                let value = self.eval(value, env)?;
+                self.eval(body, &env.extend(name.name, value))
+            }
+            ExprKind::If(cond, then, else_) => match self.eval(cond, env)? {
+                Value::Bool(true) => self.eval(then, env),
+                Value::Bool(false) => self.eval(else_, env),
+                _ => unreachable!("the condition of `if` must be `bool`"),
+            },
+            ExprKind::Block(items) => {
+                let mut value = Value::Unit;
+                for item in items.iter() {
+                    value = self.eval(item, env)?;
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    /// Applies a function to one argument, `span` is the span of the call
+    /// expression for error reporting.
+    fn apply(
+        &mut self,
+        func: Value<'tir>,
+        arg: Value<'tir>,
+        span: Span,
+    ) -> Result<Value<'tir>, RuntimeError> {
+        match func {
+            Value::Closure(closure) => {
+                let env = closure.env.extend(closure.param, arg);
+                self.eval(closure.body, &env)
+            }
+            Value::Native(native, applied) => {
+                let mut args = applied.to_vec();
+                args.push(arg);
+                if args.len() < native.arity {
+                    return Ok(Value::Native(native, args.into()));
+                }
+                (native.func)(&mut *self.out, &args)
+                    .map_err(|message| RuntimeError::new(message, span))
+            }
+            _ => unreachable!("calling a non-function value"),
+        }
+    }
+}
+
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+fn lookup<'tir>(env: &Env<'tir>, name: Symbol) -> Value<'tir> {
+    match env.lookup(name) {
+        Some(value) => value.clone(),
+        None => unreachable!("unbound variable `{}`", name),
+    }
+}
+
+fn eval_lit<'tir>(lit: &Lit) -> Value<'tir> {
+    match lit.value {
+        LitValue::Int(value) => Value::Int(value),
+        LitValue::Float(value) => Value::Float(value),
+        LitValue::String(value) => Value::String(value.as_str().into()),
+        LitValue::Char(value) => Value::Char(value),
+        LitValue::Bool(value) => Value::Bool(value),
+        LitValue::Err => unreachable!("invalid literal `{}`", lit.symbol),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use kona_diagnostic::DiagnosticsEngine;
+    use kona_parse::parse::parse_file;
+    use kona_source::source_map::SourceMap;
+    use kona_type_ir::{infer::InferContext, ty::context::{Arenas, TyCtxt}};
+
+    use crate::prelude;
+
+    use super::*;
+
+    /// A writer that keeps the output in memory.
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Evaluates `src` with the prelude, returns the result (the value or the
+    /// error message) and the output.
+    fn run(src: &str) -> (Result<String, String>, String) {
+        let sm = SourceMap::new();
+        let prelude_file = prelude::load_prelude(&sm);
+        let file = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let diag = DiagnosticsEngine::new(Rc::new(sm));
+        let expr = parse_file(&file, &diag);
+
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let mut infcx = InferContext::new(&tcx, &diag);
+        let output = Output::default();
+        let mut interpreter = Interpreter::with_output(Box::new(output.clone()));
+        prelude::define_prelude(&prelude_file, &diag, &tcx, &mut infcx, &mut interpreter)
+            .unwrap();
+        let expr = infcx.infer_program(&expr);
+        diag.flush();
+        assert!(!diag.has_errors(), "errors in {:?}", src);
+
+        let result = interpreter
+            .eval_program(expr)
+            .map(|value| value.to_string())
+            .map_err(|err| err.message);
+        let output = String::from_utf8(output.0.take()).unwrap();
+        (result, output)
+    }
+
+    fn assert_value(src: &str, expected: &str) {
+        assert_eq!(run(src).0.as_deref(), Ok(expected), "wrong value of {:?}", src);
+    }
+
+    fn assert_runtime_err(src: &str, expected: &str) {
+        let error = run(src).0.err();
+        assert_eq!(error.as_deref(), Some(expected), "wrong error of {:?}", src);
+    }
+
+    #[test]
+    fn test_eval_values() {
+        assert_value("()", "()");
+        assert_value("42", "42");
+        assert_value("-1.5", "-1.5");
+        assert_value("1.0", "1.0");
+        assert_value("false", "false");
+        assert_value(r#""a\tb\"""#, r#""a\tb\"""#);
+        assert_value(r"'\''", r"'\''");
+        assert_value(r"'\u{3bb}'", "'λ'");
+        assert_value("fn x => x", "<fn>");
+    }
+
+    #[test]
+    fn test_eval_exprs() {
+        assert_value("if true then 1 else 2", "1");
+        assert_value("if false then 1 else 2", "2");
+        assert_value("(1; 2; 3)", "3");
+        assert_value("(1; 2;)", "()");
+        assert_value("let x = 1 in let x = 2 in x", "2");
+        assert_value("(fn x => fn y => x) 1 2", "1");
+        assert_value(r#""a" ^ "b" ^ "c""#, r#""abc""#);
+
+        // Closures capture the environment where they are created.
+        assert_value("let x = 1 in let f = fn y => x in let x = 2 in f ()", "1");
+
+        // Native functions can be partially applied.
+        assert_value(
+            r#"let f = fn a => fn b => a ^ b in let g = f "a" in (g "b"; g "c")"#,
+            r#""ac""#,
+        );
+    }
+
+    #[test]
+    fn test_eval_prelude() {
+        assert_value("1 + 2 * 3 - 4 / 2 - 7 % 4", "2");
+        assert_value("0.5 +. 1.0 *. 2.0 /. 4.0 -. 1.0", "0.0");
+        assert_value("1 = 1 && 1.5 < 2.0 && \"b\" > \"a\" && not (1 <> 1)", "true");
+        assert_value("(1 <= 1; \"a\" >= \"b\")", "false");
+        assert_value("not (() = ())", "false");
+        assert_value("(0.0 /. 0.0) = (0.0 /. 0.0)", "false");
+        assert_value("min 1 2 + max 1 2 + abs (negate 3)", "6");
+        assert_value("intToString 42 ^ floatToString 1.0", "\"421.0\"");
+        assert_value("floatToInt (-2.5) + stringToInt \"3\"", "1");
+        assert_value("intToFloat 2 *. stringToFloat \"1.5\"", "3.0");
+        assert_value("boolToString (true && false || true)", "\"true\"");
+        assert_value("charToString 'a' ^ charToString (intToChar (charToInt 'a' + 1))", "\"ab\"");
+        assert_value("'a' < 'b' && 'é' = '\u{e9}'", "true");
+
+        // The right operand is not evaluated if the left one decides.
+        assert_value("false && 1 / 0 = 0", "false");
+        assert_value("true || 1 / 0 = 0", "true");
+
+        assert_runtime_err("1 / 0", "attempt to divide by zero");
+        assert_runtime_err("9223372036854775807 + 1", "attempt to add with overflow");
+        assert_runtime_err("stringToInt \"x\"", "cannot convert \"x\" to `int`");
+        assert_runtime_err("floatToInt (1.0 /. 0.0)", "cannot convert `inf` to `int`");
+        assert_runtime_err("intToChar 55296", "`55296` is not a Unicode scalar value");
+
+        let (_, output) = run("(print \"a\"; print \"b\"; println \"c\")");
+        assert_eq!(output, "abc\n");
+    }
+
+    #[test]
+    fn test_eval_hello() {
+        let (value, output) = run(include_str!("../../../examples/hello.kona"));
+        assert_eq!(value.as_deref(), Ok("()"));
+        assert_eq!(output, "Hello, Izumi!\n");
+    }
+}
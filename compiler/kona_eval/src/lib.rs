@@ -1,2 +1,8 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
+
+pub mod builtins;
+pub mod env;
+pub mod interpreter;
+pub mod prelude;
+pub mod value;
@@ -0,0 +1,160 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The prelude, the variables visible to every program. It consists of the
+//! [`BUILTINS`] implemented in Rust, and the declarations in `prelude.kona`
+//! written in Kona.
+
+use std::{iter::Peekable, rc::Rc};
+
+use kona_diagnostic::DiagnosticsEngine;
+use kona_memory::intern::symbol::Symbol;
+use kona_parse::parse::parse_decls;
+use kona_source::{source_file::SourceFile, source_map::SourceMap};
+use kona_type_ir::{
+    infer::InferContext,
+    ty::{context::TyCtxt, ty::{Ty, TyScheme}},
+};
+
+use crate::{builtins::BUILTINS, interpreter::{Interpreter, RuntimeError}};
+
+/// The source code of the prelude.
+pub const SOURCE: &str = include_str!("prelude.kona");
+
+/// Loads the prelude into the source map as a virtual file.
+pub fn load_prelude(sm: &SourceMap) -> Rc<SourceFile> {
+    sm.load_virtual_file("prelude.kona".to_string(), SOURCE.to_string())
+}
+
+/// Defines the prelude loaded by [`load_prelude`] in both the type checker and
+/// the interpreter, the built-in functions first, then the declarations.
+///
+/// Errors in the prelude are reported to `diag` like the errors in any other
+/// file. The declarations are not evaluated if there are errors.
+pub fn define_prelude<'tcx>(
+    file: &SourceFile,
+    diag: &DiagnosticsEngine,
+    tcx: &TyCtxt<'tcx>,
+    infcx: &mut InferContext<'_, 'tcx>,
+    interpreter: &mut Interpreter<'tcx>,
+) -> Result<(), RuntimeError> {
+    for builtin in BUILTINS {
+        let name = Symbol::intern(builtin.native.name);
+        infcx.define(name, parse_signature(tcx, builtin.signature));
+        interpreter.define_native(&builtin.native);
+    }
+
+    let decls: Vec<_> = parse_decls(file, diag)
+        .iter()
+        .map(|decl| {
+            let decl = infcx.infer_decl(decl);
+            infcx.define(decl.name.name, decl.scheme);
+            decl
+        })
+        .collect();
+    if diag.has_errors() {
+        return Ok(());
+    }
+    for decl in &decls {
+        interpreter.eval_decl(decl)?;
+    }
+    Ok(())
+}
+
+/// Parses the signature of a built-in function, e.g. `''a -> ''a -> bool`.
+/// The type variables are quantified, the ones written with two quotes, e.g.
+/// `''a`, are comparable.
+///
+/// # Panics
+///
+/// Panics if the signature is malformed, they are all written in [`BUILTINS`].
+fn parse_signature<'tcx>(tcx: &TyCtxt<'tcx>, signature: &str) -> TyScheme<'tcx> {
+    let spaced = signature.replace('(', " ( ").replace(')', " ) ");
+    let mut tokens = spaced.split_whitespace().peekable();
+    let mut names = Vec::new();
+    let ty = parse_ty(tcx, &mut tokens, &mut names);
+    assert!(tokens.next().is_none(), "malformed signature `{}`", signature);
+
+    let vars = ty.ty_vars();
+    if vars.is_empty() {
+        TyScheme::Mono(ty)
+    } else {
+        TyScheme::Poly(tcx.alloc_ty_vars(vars), ty)
+    }
+}
+
+/// ```text
+/// type ::= atomic-type | atomic-type '->' type
+/// atomic-type ::= name | type-variable | '(' type ')'
+/// type-variable ::= "'" name | "''" name
+/// ```
+fn parse_ty<'a, 'tcx>(
+    tcx: &TyCtxt<'tcx>,
+    tokens: &mut Peekable<impl Iterator<Item = &'a str>>,
+    names: &mut Vec<(&'a str, &'tcx Ty<'tcx>)>,
+) -> &'tcx Ty<'tcx> {
+    let param = match tokens.next() {
+        Some("(") => {
+            let ty = parse_ty(tcx, tokens, names);
+            assert_eq!(tokens.next(), Some(")"), "unclosed parenthesis");
+            ty
+        }
+        Some(var) if var.starts_with('\'') => {
+            match names.iter().find(|(name, _)| *name == var) {
+                Some((_, ty)) => ty,
+                None => {
+                    let ty = if var.starts_with("''") {
+                        tcx.mk_comparable_ty_var(0)
+                    } else {
+                        tcx.mk_ty_var(0)
+                    };
+                    names.push((var, ty));
+                    ty
+                }
+            }
+        }
+        Some("unit") => tcx.types.unit,
+        Some("bool") => tcx.types.bool,
+        Some("int") => tcx.types.int,
+        Some("float") => tcx.types.float,
+        Some("char") => tcx.types.char,
+        Some("string") => tcx.types.string,
+        token => panic!("unexpected {:?} in the signature", token),
+    };
+
+    if tokens.next_if_eq(&"->").is_some() {
+        tcx.mk_fn(param, parse_ty(tcx, tokens, names))
+    } else {
+        param
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kona_type_ir::ty::context::Arenas;
+
+    use super::*;
+
+    #[test]
+    fn test_builtin_signatures() {
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        assert_eq!(parse_signature(&tcx, "int").to_string(), "int");
+        assert_eq!(
+            parse_signature(&tcx, "('b -> 'a) -> 'b -> 'a").to_string(),
+            "('a -> 'b) -> 'a -> 'b",
+        );
+        assert_eq!(parse_signature(&tcx, "''a -> 'b -> ''a").to_string(), "''a -> 'b -> ''a");
+
+        // The arity of a native function is the number of its parameters.
+        for builtin in BUILTINS {
+            let mut ty = parse_signature(&tcx, builtin.signature).ty();
+            let mut arity = 0;
+            while let Some((_, ret)) = ty.fn_sig() {
+                ty = ret;
+                arity += 1;
+            }
+            assert_eq!(arity, builtin.native.arity, "wrong arity of `{}`", builtin.native.name);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::{fmt, io::Write, rc::Rc};
+
+use kona_memory::intern::symbol::Symbol;
+use kona_type_ir::tir::expr::Expr;
+
+use crate::env::Env;
+
+/// A runtime value.
+#[derive(Clone)]
+pub enum Value<'tir> {
+    Unit,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(Rc<str>),
+    Char(char),
+    Closure(Rc<Closure<'tir>>),
+
+    /// A native function with the arguments applied so far, it is called once
+    /// all its arguments are applied.
+    Native(&'static NativeFn, Rc<[Value<'tir>]>),
+}
+
+/// A function value created by a lambda expression.
+pub struct Closure<'tir> {
+    pub param: Symbol,
+    pub body: &'tir Expr<'tir>,
+
+    /// The captured environment where the lambda is evaluated.
+    pub env: Env<'tir>,
+}
+
+/// A function implemented in Rust.
+pub struct NativeFn {
+    pub name: &'static str,
+
+    /// The number of arguments, native functions are curried like the other
+    /// functions.
+    pub arity: usize,
+
+    /// The implementation, it takes exactly `arity` arguments of the right
+    /// types, and writes the output (if any) to `out`. Errors are returned as
+    /// messages, the interpreter adds the location.
+    pub func: for<'tir> fn(&mut dyn Write, &[Value<'tir>]) -> Result<Value<'tir>, String>,
+}
+
+impl fmt::Display for Value<'_> {
+    /// Formats the value the way it is written in Kona, e.g. strings are
+    /// quoted.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{:?}", value),
+            Value::Char(value) => write!(f, "{:?}", value),
+            Value::Closure(_) | Value::Native(..) => write!(f, "<fn>"),
+        }
+    }
+}
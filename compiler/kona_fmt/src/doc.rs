@@ -0,0 +1,241 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! A document of layout choices and its printer, in the style of Wadler's
+//! "A prettier printer".
+//!
+//! A [`Doc`] describes the text to print and where lines may break. A
+//! [`Doc::Group`] is printed flat, on one line, if it fits in the line width,
+//! otherwise its [`Doc::Line`]s become line breaks. Comments are part of the
+//! document, a line comment forces the groups around it to break.
+
+/// The indentation of a nested level.
+pub const INDENT: usize = 2;
+
+/// The maximum line width the printer tries to keep.
+pub const WIDTH: usize = 80;
+
+pub enum Doc {
+    Nil,
+
+    /// Text without line breaks.
+    Text(String),
+
+    /// A space in a flat group, or a line break.
+    Line,
+
+    /// Nothing in a flat group, or a line break.
+    SoftLine,
+
+    /// Always a line break.
+    HardLine,
+
+    /// A line break followed by an empty line.
+    BlankLine,
+    Concat(Vec<Doc>),
+
+    /// Indents the line breaks in the document by [`INDENT`].
+    Nest(Box<Doc>),
+    Group(Box<Doc>),
+
+    /// A comment on its own line. It is moved to a new line if the printer is
+    /// not at the start of one, and the next text goes on a new line too.
+    OwnLineComment {
+        text: String,
+        blank_before: bool,
+        blank_after: bool,
+    },
+
+    /// A comment after code on the same line. A line comment is delayed to
+    /// the end of the line, and the next text goes on a new line.
+    TrailingComment { text: String, is_line_comment: bool },
+}
+
+impl Doc {
+    pub fn text(text: impl Into<String>) -> Doc {
+        Doc::Text(text.into())
+    }
+
+    pub fn nest(doc: Doc) -> Doc {
+        Doc::Nest(Box::new(doc))
+    }
+
+    pub fn group(doc: Doc) -> Doc {
+        Doc::Group(Box::new(doc))
+    }
+
+    /// Returns true if the document can't be printed on one line.
+    fn has_hard_break(&self) -> bool {
+        match self {
+            Doc::Nil | Doc::Text(_) | Doc::Line | Doc::SoftLine => false,
+            Doc::HardLine | Doc::BlankLine | Doc::OwnLineComment { .. } => true,
+            Doc::TrailingComment { text, is_line_comment } => {
+                *is_line_comment || text.contains('\n')
+            }
+            Doc::Concat(docs) => docs.iter().any(Doc::has_hard_break),
+            Doc::Nest(doc) | Doc::Group(doc) => doc.has_hard_break(),
+        }
+    }
+}
+
+impl From<&str> for Doc {
+    fn from(text: &str) -> Doc {
+        Doc::text(text)
+    }
+}
+
+impl From<Vec<Doc>> for Doc {
+    fn from(docs: Vec<Doc>) -> Doc {
+        Doc::Concat(docs)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Prints a document, the output ends with a line break.
+pub fn print(doc: &Doc) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        col: 0,
+        pending_lines: 0,
+        line_suffix: String::new(),
+    };
+    printer.print(doc);
+    printer.line_break(0);
+    let trimmed_len = printer.out.trim_end().len();
+    printer.out.truncate(trimmed_len);
+    printer.out.push('\n');
+    printer.out
+}
+
+struct Printer {
+    out: String,
+
+    /// The column of the end of the output, in characters.
+    col: usize,
+
+    /// The number of line breaks the next text must start with, 1 after a
+    /// comment which ends its line, and 2 to keep an empty line after it.
+    pending_lines: usize,
+
+    /// The trailing line comments delayed to the end of the line.
+    line_suffix: String,
+}
+
+impl Printer {
+    fn print(&mut self, doc: &Doc) {
+        let mut stack = vec![(0, Mode::Break, doc)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Nil => {}
+                Doc::Text(text) => self.write(indent, text),
+                Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                    if self.pending_lines == 0 && matches!(doc, Doc::Line) {
+                        self.out.push(' ');
+                        self.col += 1;
+                    }
+                }
+                Doc::Line | Doc::SoftLine | Doc::HardLine => self.line_break(indent),
+                Doc::BlankLine => {
+                    self.pending_lines = 2;
+                    self.line_break(indent);
+                }
+                Doc::Concat(docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+                }
+                Doc::Nest(doc) => stack.push((indent + INDENT, mode, doc)),
+                Doc::Group(doc) => {
+                    let mode = if mode == Mode::Flat || self.fits(indent, doc) {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.push((indent, mode, doc));
+                }
+                Doc::OwnLineComment { text, blank_before, blank_after } => {
+                    if !self.is_at_line_start() {
+                        self.pending_lines = if *blank_before { 2 } else { 1 };
+                    } else if *blank_before && !self.out.trim().is_empty()
+                        && !self.out.trim_end_matches(' ').ends_with("\n\n")
+                    {
+                        // Adds an empty line before the comment.
+                        self.pending_lines = 1;
+                    }
+                    self.write(indent, text);
+                    self.pending_lines = if *blank_after { 2 } else { 1 };
+                }
+                Doc::TrailingComment { text, is_line_comment: true } => {
+                    self.line_suffix.push(' ');
+                    self.line_suffix.push_str(text);
+                    self.pending_lines = self.pending_lines.max(1);
+                }
+                Doc::TrailingComment { text, is_line_comment: false } => {
+                    self.write(indent, &format!(" {}", text));
+                }
+            }
+        }
+    }
+
+    /// Returns true if the group fits in the rest of the line when it is
+    /// printed flat.
+    ///
+    /// Only the group itself is measured, the text after it may go past the
+    /// line width, e.g. the closing parenthesis of the enclosing group.
+    fn fits(&self, indent: usize, doc: &Doc) -> bool {
+        if doc.has_hard_break() {
+            return false;
+        }
+        // The group starts on a new line after a comment.
+        let mut width = if self.pending_lines > 0 { indent } else { self.col };
+        let mut stack = vec![doc];
+        while let Some(doc) = stack.pop() {
+            match doc {
+                Doc::Text(text) => width += text.chars().count(),
+                Doc::TrailingComment { text, .. } => width += text.chars().count() + 1,
+                Doc::Line => width += 1,
+                Doc::Concat(docs) => stack.extend(docs.iter().rev()),
+                Doc::Nest(doc) | Doc::Group(doc) => stack.push(doc),
+                _ => {}
+            }
+            if width > WIDTH {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn write(&mut self, indent: usize, mut text: &str) {
+        if self.pending_lines > 0 {
+            self.line_break(indent);
+            text = text.trim_start_matches(' ');
+        }
+        self.out.push_str(text);
+        match text.rfind('\n') {
+            Some(index) => self.col = text[index + 1..].chars().count(),
+            None => self.col += text.chars().count(),
+        }
+    }
+
+    fn line_break(&mut self, indent: usize) {
+        let trimmed_len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed_len);
+        let suffix = std::mem::take(&mut self.line_suffix);
+        self.out.push_str(&suffix);
+
+        let lines = if self.out.is_empty() { 0 } else { self.pending_lines.max(1) };
+        for _ in 0..lines {
+            self.out.push('\n');
+        }
+        self.pending_lines = 0;
+        self.out.push_str(&" ".repeat(indent));
+        self.col = indent;
+    }
+
+    fn is_at_line_start(&self) -> bool {
+        self.out.trim_end_matches(' ').ends_with('\n') || self.out.trim().is_empty()
+    }
+}
@@ -0,0 +1,491 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The canonical formatter of Kona, `kona fmt`.
+//!
+//! The formatter parses a file and reprints its concrete syntax tree, see
+//! `kona_syntax`. The comments are trivia tokens in the tree, between the
+//! children of a node, and a comment is printed before the child after it. A
+//! comment on its own line stays on its own line, and a comment after code
+//! stays at the end of the line.
+
+pub mod doc;
+
+use kona_diagnostic::DiagnosticsEngine;
+use kona_parse::parse::parse_items_with_cst;
+use kona_source::source_file::SourceFile;
+use kona_syntax::{
+    ast::{self, AstNode, Expr},
+    kind::SyntaxKind,
+    node::{SyntaxElement, SyntaxNode, SyntaxToken},
+};
+
+use self::doc::Doc;
+
+/// Formats a source file, a program or a sequence of declarations like the
+/// prelude. Returns `None` if there are syntax errors, which are reported to
+/// `diag`.
+pub fn format_file(file: &SourceFile, diag: &DiagnosticsEngine) -> Option<String> {
+    let err_count = diag.err_count();
+    let (_, tree) = parse_items_with_cst(file, diag);
+    if diag.err_count() > err_count {
+        return None;
+    }
+
+    let root = ast::Root::cast(tree).expect("the parser builds a root node");
+    let mut docs = Vec::new();
+    if let Some(expr) = root.expr() {
+        docs.push(comments_before(expr.syntax().clone()));
+        docs.push(format_expr(&expr));
+    }
+    for (index, decl) in root.decls().enumerate() {
+        docs.push(comments_before(decl.syntax().clone()));
+        if index > 0 {
+            docs.push(line_before(decl.syntax()));
+        }
+        docs.push(format_decl(&decl));
+    }
+    docs.push(comments_at_end(root.syntax()));
+    Some(doc::print(&docs.into()))
+}
+
+fn format_expr(expr: &Expr) -> Doc {
+    match expr {
+        Expr::Error(_) => unreachable!("formatting an erroneous expression"),
+        Expr::Unit(unit) => {
+            vec!["(".into(), comments_before(part(unit.r_paren())), ")".into()].into()
+        }
+        Expr::Lit(lit) => Doc::text(lit.syntax().text()),
+        Expr::NameRef(name) => Doc::text(part(name.ident()).text()),
+        Expr::Infix(infix) => format_infix(infix),
+        Expr::Fixity(fixity) => {
+            let mut head = vec![Doc::text(part(fixity.fixity()).text())];
+            match fixity.precedence() {
+                Some(prec) => {
+                    head.push(comments_before(prec.clone()));
+                    head.push(Doc::text(format!(" {}", prec.text())));
+                }
+                // The default precedence is written out.
+                None => head.push(" 0".into()),
+            }
+            for op in fixity.operators() {
+                head.push(comments_before(op.clone()));
+                head.push(Doc::text(format!(" {}", op.text())));
+            }
+            head.push(comments_before(part(fixity.in_kw())));
+            head.push(" in".into());
+            format_body_after(Doc::group(head.into()), &part(fixity.body()))
+        }
+        Expr::Fn(fn_expr) => format_fn(fn_expr.clone()),
+        Expr::Call(call) => {
+            // A parenthesized last argument stays on the line of the
+            // function, and breaks inside the parentheses, e.g. `println (`
+            // followed by the indented contents.
+            let mut args: Vec<_> = call.args().collect();
+            let hugged = match args.last() {
+                Some(Expr::Paren(_) | Expr::Block(_)) => args.pop(),
+                _ => None,
+            };
+            let mut docs = vec![format_expr(&part(call.func()))];
+            let args = args.iter().map(line_then).collect();
+            docs.push(Doc::nest(Doc::Concat(args)));
+            if let Some(arg) = hugged {
+                docs.push(comments_before(arg.syntax().clone()));
+                docs.push(" ".into());
+                docs.push(format_expr(&arg));
+            }
+            Doc::group(docs.into())
+        }
+        Expr::If(if_expr) => format_if(if_expr.clone()),
+        Expr::Paren(paren) => {
+            let inner = part(paren.expr());
+            let contents = vec![
+                comments_before(inner.syntax().clone()),
+                Doc::SoftLine,
+                format_expr(&inner),
+            ];
+            format_delimited(contents, part(paren.r_paren()))
+        }
+        Expr::Block(block) => {
+            // The items and the `;`s are printed as they are in the tree,
+            // including a trailing `;`.
+            let mut docs = Vec::new();
+            let mut first = true;
+            for element in block.syntax().children_with_tokens() {
+                if element.kind() == SyntaxKind::Semi {
+                    docs.push(comments_before(element));
+                    docs.push(";".into());
+                } else if let Some(item) = element.into_node().and_then(Expr::cast) {
+                    docs.push(comments_before(item.syntax().clone()));
+                    docs.push(if first { Doc::SoftLine } else { Doc::Line });
+                    docs.push(format_expr(&item));
+                } else {
+                    continue;
+                }
+                first = false;
+            }
+            format_delimited(docs, part(block.r_paren()))
+        }
+        Expr::Let(let_expr) => {
+            let name = part(let_expr.name());
+            let value = part(let_expr.value());
+            let head = Doc::group(vec![
+                "let".into(),
+                comments_before(name.clone()),
+                Doc::text(format!(" {}", name.text())),
+                comments_before(part(let_expr.eq_token())),
+                " =".into(),
+                Doc::nest(line_then(&value)),
+                comments_before(part(let_expr.in_kw())),
+                Doc::Line,
+                "in".into(),
+            ].into());
+            format_body_after(head, &part(let_expr.body()))
+        }
+    }
+}
+
+/// Formats a top-level declaration, like the head of a `let` expression.
+fn format_decl(decl: &ast::Decl) -> Doc {
+    let name = part(decl.name());
+    Doc::group(vec![
+        "let".into(),
+        comments_before(name.clone()),
+        Doc::text(format!(" {}", name.text())),
+        comments_before(part(decl.eq_token())),
+        " =".into(),
+        Doc::nest(line_then(&part(decl.value()))),
+    ].into())
+}
+
+/// Formats the body of a `let` or a fixity declaration on the next line,
+/// without indentation, so that a chain of them reads top-down. An empty line
+/// after `in` is kept.
+fn format_body_after(head: Doc, body: &Expr) -> Doc {
+    let line = line_before(body.syntax());
+    vec![head, comments_before(body.syntax().clone()), line, format_expr(body)].into()
+}
+
+/// Returns the line break before a node which starts a line, an empty line is
+/// kept. The empty lines around the comments are kept by the comments.
+fn line_before(node: &SyntaxNode) -> Doc {
+    let (trivia, _) = trivia_before(node.clone());
+    if !trivia.iter().any(|token| token.kind().is_comment()) && line_breaks(trivia.iter()) >= 2 {
+        Doc::BlankLine
+    } else {
+        Doc::HardLine
+    }
+}
+
+/// Formats a chain of infix operators, a line may break before every
+/// operator. The operators are not resolved in the syntax tree, and the
+/// parentheses are explicit, so the chain is flat.
+fn format_infix(infix: &ast::InfixExpr) -> Doc {
+    let mut operands = infix.operands();
+    let first = format_expr(&part(operands.next()));
+    let mut rest = Vec::new();
+    for (op, operand) in infix.operators().zip(operands) {
+        rest.push(comments_before(op.clone()));
+        rest.push(Doc::Line);
+        rest.push(Doc::text(format!("{} ", op.text())));
+        rest.push(comments_before(operand.syntax().clone()));
+        rest.push(format_expr(&operand));
+    }
+    Doc::group(vec![first, Doc::nest(rest.into())].into())
+}
+
+/// Formats a lambda, the curried parameters stay on the first line, e.g.
+/// `fn a => fn b =>`, and the body goes on the next line if it doesn't fit.
+fn format_fn(mut fn_expr: ast::FnExpr) -> Doc {
+    let mut head = Vec::new();
+    loop {
+        let param = part(fn_expr.param());
+        head.push("fn".into());
+        head.push(comments_before(param.clone()));
+        head.push(Doc::text(format!(" {}", param.text())));
+        head.push(comments_before(part(fn_expr.arrow())));
+        head.push(" =>".into());
+        match part(fn_expr.body()) {
+            Expr::Fn(inner) => {
+                head.push(comments_before(inner.syntax().clone()));
+                head.push(" ".into());
+                fn_expr = inner;
+            }
+            body => {
+                let body = line_then(&body);
+                return Doc::group(Doc::nest(vec![head.into(), body].into()));
+            }
+        }
+    }
+}
+
+/// Formats an `if`, a chain of `else if` is formatted flat:
+///
+/// ```text
+/// if a then
+///   b
+/// else if c then
+///   d
+/// else
+///   e
+/// ```
+fn format_if(mut if_expr: ast::IfExpr) -> Doc {
+    let mut docs = Vec::new();
+    loop {
+        let condition = part(if_expr.condition());
+        docs.push("if ".into());
+        docs.push(Doc::nest(vec![
+            comments_before(condition.syntax().clone()),
+            format_expr(&condition),
+        ].into()));
+        docs.push(comments_before(part(if_expr.then_kw())));
+        docs.push(" then".into());
+        docs.push(Doc::nest(line_then(&part(if_expr.then_branch()))));
+        docs.push(comments_before(part(if_expr.else_kw())));
+        docs.push(Doc::Line);
+        docs.push("else".into());
+        match part(if_expr.else_branch()) {
+            Expr::If(else_if) => {
+                docs.push(comments_before(else_if.syntax().clone()));
+                docs.push(" ".into());
+                if_expr = else_if;
+            }
+            else_branch => {
+                docs.push(Doc::nest(line_then(&else_branch)));
+                return Doc::group(docs.into());
+            }
+        }
+    }
+}
+
+/// Formats a parenthesized expression or a block, the contents are indented
+/// on their own lines if they don't fit.
+fn format_delimited(contents: Vec<Doc>, r_paren: SyntaxToken) -> Doc {
+    Doc::group(vec![
+        "(".into(),
+        Doc::nest(contents.into()),
+        comments_before(r_paren),
+        Doc::SoftLine,
+        ")".into(),
+    ].into())
+}
+
+/// Formats an expression after a line, the comments before the expression go
+/// before the line.
+fn line_then(expr: &Expr) -> Doc {
+    vec![comments_before(expr.syntax().clone()), Doc::Line, format_expr(expr)].into()
+}
+
+/// Returns a part of a node, which is always there in a tree without syntax
+/// errors.
+fn part<T>(part: Option<T>) -> T {
+    part.expect("a missing part in a tree without syntax errors")
+}
+
+/// Formats the comments between `element` and the previous child of its
+/// parent.
+fn comments_before(element: impl Into<SyntaxElement>) -> Doc {
+    let (trivia, code_before) = trivia_before(element);
+    format_comments(&trivia, code_before, true)
+}
+
+/// Formats the comments after the last expression of the file.
+fn comments_at_end(root: &SyntaxNode) -> Doc {
+    let children: Vec<_> = root.children_with_tokens().collect();
+    let mut trivia: Vec<_> = children
+        .iter()
+        .rev()
+        .map_while(|element| element.clone().into_token().filter(|token| token.kind().is_trivia()))
+        .collect();
+    trivia.reverse();
+    format_comments(&trivia, trivia.len() < children.len(), false)
+}
+
+/// Returns the trivia tokens between `element` and the previous child of its
+/// parent in the source order, and whether there is such a child. The parser
+/// never puts trivia at the start or the end of a node, so these are all the
+/// trivia in between.
+fn trivia_before(element: impl Into<SyntaxElement>) -> (Vec<SyntaxToken>, bool) {
+    let mut trivia = Vec::new();
+    let mut code_before = false;
+    for sibling in element.into().prev_siblings_with_tokens() {
+        match sibling.into_token() {
+            Some(token) if token.kind().is_trivia() => trivia.push(token),
+            _ => {
+                code_before = true;
+                break;
+            }
+        }
+    }
+    trivia.reverse();
+    (trivia, code_before)
+}
+
+/// Formats the comments in a run of trivia. `code_before` and `code_after`
+/// tell if there is code right before and after the run.
+fn format_comments(trivia: &[SyntaxToken], code_before: bool, code_after: bool) -> Doc {
+    let mut docs = Vec::new();
+    for (index, token) in trivia.iter().enumerate() {
+        if !token.kind().is_comment() {
+            continue;
+        }
+        let text = token.text().trim_end().to_string();
+        let is_line_comment =
+            matches!(token.kind(), SyntaxKind::LineComment | SyntaxKind::LineDocComment);
+
+        // The comment is a trailing one if there is code or another comment
+        // before it on the same line.
+        let before = trivia[..index]
+            .iter()
+            .rev()
+            .find(|token| token.kind() != SyntaxKind::Whitespace);
+        if before.map_or(code_before, |token| token.kind() != SyntaxKind::Eol) {
+            docs.push(Doc::TrailingComment { text, is_line_comment });
+            continue;
+        }
+
+        let after = &trivia[index + 1..];
+        let blank_before = (before.is_some() || code_before)
+            && line_breaks(trivia[..index].iter().rev()) >= 2;
+        let blank_after = (code_after || after.iter().any(|token| !is_blank(token)))
+            && line_breaks(after.iter()) >= 2;
+        docs.push(Doc::OwnLineComment { text, blank_before, blank_after });
+    }
+    docs.into()
+}
+
+/// Counts the line breaks in the whitespace at the start of `trivia`.
+fn line_breaks<'a>(trivia: impl Iterator<Item = &'a SyntaxToken>) -> usize {
+    trivia
+        .take_while(|token| is_blank(token))
+        .filter(|token| token.kind() == SyntaxKind::Eol)
+        .count()
+}
+
+fn is_blank(token: &SyntaxToken) -> bool {
+    matches!(token.kind(), SyntaxKind::Whitespace | SyntaxKind::Eol)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use kona_source::source_map::SourceMap;
+
+    use super::*;
+
+    /// Formats `src`, and checks that formatting the result again changes
+    /// nothing.
+    fn format(src: &str) -> String {
+        let format_once = |src: &str| {
+            let sm = Rc::new(SourceMap::new());
+            let file = sm.load_virtual_file("<test>".to_string(), src.to_string());
+            let diag = DiagnosticsEngine::new(sm);
+            format_file(&file, &diag).expect("syntax errors")
+        };
+        let formatted = format_once(src);
+        assert_eq!(format_once(&formatted), formatted, "not idempotent");
+        formatted
+    }
+
+    #[test]
+    fn test_format_layout() {
+        assert_eq!(format("let  x=1 in\n\n\nx+  1"), "let x = 1 in\n\nx + 1\n");
+        assert_eq!(format("(a;b;)"), "(a; b;)\n");
+        assert_eq!(format("infixr 5 ++ in a ++ b"), "infixr 5 ++ in\na ++ b\n");
+        assert_eq!(
+            format(concat!(
+                "let describe = fn n => if n < 0 then \"a negative number\" else if n = 0 ",
+                "then \"zero\" else \"a positive number\" in describe 42",
+            )),
+            concat!(
+                "let describe =\n",
+                "  fn n =>\n",
+                "    if n < 0 then\n",
+                "      \"a negative number\"\n",
+                "    else if n = 0 then\n",
+                "      \"zero\"\n",
+                "    else\n",
+                "      \"a positive number\"\n",
+                "in\n",
+                "describe 42\n",
+            ),
+        );
+        assert_eq!(
+            format(concat!(
+                "println (\"The quick brown fox \" ^ \"jumps over \" ^ ",
+                "\"the lazy dog\" ^ \", and runs away \" ^ intToString 42)",
+            )),
+            concat!(
+                "println (\n",
+                "  \"The quick brown fox \"\n",
+                "    ^ \"jumps over \"\n",
+                "    ^ \"the lazy dog\"\n",
+                "    ^ \", and runs away \"\n",
+                "    ^ intToString 42\n",
+                ")\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let src = concat!(
+            "-- A header.\n",
+            "\n",
+            "let x = 1 in   -- One.\n",
+            "let f = fn y => /- Inline. -/ y in\n",
+            "(\n",
+            "  -- First.\n",
+            "  f x;\n",
+            "\n",
+            "  -- Second.\n",
+            "  f 2\n",
+            ")\n",
+            "-- The end.\n",
+        );
+        assert_eq!(format(src), concat!(
+            "-- A header.\n",
+            "\n",
+            "let x = 1 in -- One.\n",
+            "let f = fn y => /- Inline. -/ y in\n",
+            "(\n",
+            "  -- First.\n",
+            "  f x;\n",
+            "\n",
+            "  -- Second.\n",
+            "  f 2\n",
+            ")\n",
+            "-- The end.\n",
+        ));
+    }
+
+    #[test]
+    fn test_format_keeps_all_comments() {
+        let src = concat!(
+            "infixl /- 1 -/ 6 /- 2 -/ ++ /- 3 -/ in /- 4 -/\n",
+            "let /- 5 -/ x /- 6 -/ = /- 7 -/ 1 /- 8 -/ in /- 9 -/\n",
+            "if /- 10 -/ x /- 11 -/ then /- 12 -/ (/- 13 -/ a /- 14 -/; /- 15 -/ b /- 16 -/)\n",
+            "else /- 17 -/ fn /- 18 -/ y /- 19 -/ => /- 20 -/ fn z => y /- 21 -/ ++ /- 22 -/ ",
+            "f /- 23 -/ (/- 24 -/) /- 25 -/\n",
+        );
+        let formatted = format(src);
+        let comments = formatted
+            .split("/- ")
+            .skip(1)
+            .map(|comment| comment.split(' ').next().unwrap().parse().unwrap())
+            .collect::<Vec<u32>>();
+        assert_eq!(comments, (1..=25).collect::<Vec<_>>(), "{}", formatted);
+        assert_eq!(format("(;a;b)"), "(; a; b)\n");
+    }
+
+    #[test]
+    fn test_format_decls() {
+        assert_eq!(
+            format("-- Two.\nlet  x=1\n\n\nlet y = x+1 -- Three.\nlet z=y"),
+            "-- Two.\nlet x = 1\n\nlet y = x + 1 -- Three.\nlet z = y\n",
+        );
+        let prelude = include_str!("../../kona_eval/src/prelude.kona");
+        assert_eq!(format(prelude), prelude);
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::{
+    alloc::{self, Layout},
+    cell::{Cell, RefCell},
+    cmp, mem,
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// An arena of `Copy` values of any types, the values live as long as the
+/// arena. They have no destructors, so they are never dropped.
+///
+/// The values are bump-allocated in chunks of raw memory. A full chunk is
+/// kept as is and a larger chunk is started, so the values never move.
+///
+/// ```
+/// # use kona_memory::arena::dropless_arena::DroplessArena;
+/// let arena = DroplessArena::new();
+/// let a: &mut u8 = arena.alloc(1);
+/// let bc: &mut [u64] = arena.alloc_slice([2, 3]);
+/// *a += 1;
+/// assert_eq!((*a, bc.len()), (2, 2));
+/// ```
+pub struct DroplessArena {
+    /// The start of the free space in the current chunk.
+    start: Cell<*mut u8>,
+
+    /// The end of the current chunk.
+    end: Cell<*mut u8>,
+
+    /// All chunks, they are kept only to be freed with the arena.
+    chunks: RefCell<Vec<Chunk>>,
+}
+
+struct Chunk {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+/// The size of the first chunk in bytes.
+const INITIAL_CHUNK_SIZE: usize = 4096;
+
+/// The size a chunk stops doubling at, unless a larger value needs it.
+const MAX_CHUNK_SIZE: usize = 2 * 1024 * 1024;
+
+impl Default for DroplessArena {
+    fn default() -> Self {
+        DroplessArena::new()
+    }
+}
+
+impl DroplessArena {
+    pub fn new() -> DroplessArena {
+        DroplessArena {
+            start: Cell::new(ptr::null_mut()),
+            end: Cell::new(ptr::null_mut()),
+            chunks: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T: Copy>(&self, value: T) -> &mut T {
+        let ptr = self.alloc_raw(Layout::new::<T>()).cast::<T>();
+
+        // SAFETY: The memory is allocated for a `T` and aligned, and is not
+        // used by anything else. It is freed only with the arena.
+        unsafe {
+            ptr.write(value);
+            &mut *ptr
+        }
+    }
+
+    /// Allocates the values in a contiguous slice.
+    ///
+    /// The values are collected before they are moved into the arena, so the
+    /// iterator can allocate in the same arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice<T, I>(&self, iterable: I) -> &mut [T]
+    where
+        T: Copy,
+        I: IntoIterator<Item = T>,
+    {
+        let values: Vec<T> = iterable.into_iter().collect();
+        let len = values.len();
+        if len == 0 {
+            return &mut [];
+        }
+
+        let ptr = self.alloc_raw(Layout::array::<T>(len).unwrap()).cast::<T>();
+
+        // SAFETY: The memory is allocated for `len` values of `T`, like
+        // `alloc`. The values are `Copy`, they can be copied out of the `Vec`.
+        unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr(), ptr, len);
+            slice::from_raw_parts_mut(ptr, len)
+        }
+    }
+
+    /// Returns aligned memory for the layout, from the current chunk if it
+    /// has enough space left, or from a new chunk.
+    fn alloc_raw(&self, layout: Layout) -> *mut u8 {
+        if layout.size() == 0 {
+            return ptr::without_provenance_mut(layout.align());
+        }
+        loop {
+            // The pointers are moved by `wrapping_add`, they keep the
+            // provenance of the chunk.
+            let start = self.start.get();
+            let padding = start.addr().wrapping_neg() & (layout.align() - 1);
+            let available = self.end.get().addr() - start.addr();
+            if padding.checked_add(layout.size()).is_some_and(|needed| needed <= available) {
+                let ptr = start.wrapping_add(padding);
+                self.start.set(ptr.wrapping_add(layout.size()));
+                return ptr;
+            }
+            self.grow(layout);
+        }
+    }
+
+    /// Starts a new chunk which can hold the layout.
+    #[cold]
+    fn grow(&self, layout: Layout) {
+        let mut chunks = self.chunks.borrow_mut();
+        let size = match chunks.last() {
+            Some(last) => cmp::min(last.layout.size(), MAX_CHUNK_SIZE / 2) * 2,
+            None => INITIAL_CHUNK_SIZE,
+        };
+        let required = layout.size().checked_add(layout.align()).expect("capacity overflow");
+        let layout = Layout::from_size_align(cmp::max(size, required), mem::align_of::<usize>())
+            .expect("capacity overflow");
+
+        // SAFETY: The size is not zero.
+        let ptr = unsafe { alloc::alloc(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            alloc::handle_alloc_error(layout)
+        };
+        self.start.set(ptr.as_ptr());
+        self.end.set(ptr.as_ptr().wrapping_add(layout.size()));
+        chunks.push(Chunk { ptr, layout });
+    }
+}
+
+impl Drop for DroplessArena {
+    fn drop(&mut self) {
+        for chunk in self.chunks.get_mut().drain(..) {
+            // SAFETY: The chunk is allocated in `grow` with the layout. The
+            // values in it are `Copy` and need no drop.
+            unsafe { alloc::dealloc(chunk.ptr.as_ptr(), chunk.layout) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_aligned<T>(value: &T) -> bool {
+        (value as *const T).is_aligned()
+    }
+
+    #[test]
+    fn test_alloc_mixed_types() {
+        let arena = DroplessArena::new();
+        let a = arena.alloc(1u8);
+        let b = arena.alloc(2u64);
+        let c = arena.alloc([3u8; 3]);
+        let d = arena.alloc(4u128);
+        let e = arena.alloc_slice([5u16, 6, 7]);
+        let f = arena.alloc((8i32, 'f'));
+        assert!(is_aligned(b) && is_aligned(d) && is_aligned(&e[0]) && is_aligned(f));
+
+        *a += 1;
+        f.0 += 1;
+        assert_eq!((*a, *b, *c, *d), (2, 2, [3; 3], 4));
+        assert_eq!((e as &[u16], *f), (&[5, 6, 7][..], (9, 'f')));
+    }
+
+    #[test]
+    fn test_alloc_across_chunks() {
+        let arena = DroplessArena::new();
+        let values: Vec<&mut u64> = (0..2000).map(|i| arena.alloc(i)).collect();
+        // Larger than the next chunk would be.
+        let large = arena.alloc_slice(0..INITIAL_CHUNK_SIZE as u64);
+        let bytes = arena.alloc_slice([1u8; 5]);
+
+        // The values are still valid after the chunks are full.
+        for (i, value) in values.into_iter().enumerate() {
+            assert_eq!(*value, i as u64);
+            *value = 0;
+        }
+        assert!(large.iter().enumerate().all(|(i, value)| *value == i as u64));
+        assert_eq!(bytes, &[1; 5]);
+        assert!(arena.chunks.borrow().len() > 2);
+    }
+
+    #[test]
+    fn test_alloc_zero_sized_types() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(align(64))]
+        struct Aligned;
+
+        let arena = DroplessArena::new();
+        assert_eq!(arena.alloc(()), &mut ());
+        assert!(is_aligned(arena.alloc(Aligned)));
+        assert_eq!(arena.alloc_slice([(), ()]).len(), 2);
+        assert_eq!(arena.alloc_slice(Vec::<u64>::new()), &mut []);
+        assert!(arena.chunks.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_alloc_slice_reentrant() {
+        let arena = DroplessArena::new();
+        let values: &[&u32] = arena.alloc_slice((0..10).map(|i| &*arena.alloc(i)));
+        assert_eq!(values.iter().copied().sum::<u32>(), 45);
+    }
+}
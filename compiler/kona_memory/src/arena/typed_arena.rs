@@ -1,31 +1,58 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use std::{cell::RefCell, cmp, mem, iter};
-
-// NOTE: `Vec<T>` may move to other memory during resize. We need to avoid this
-// situation for our `Vec`-based arena.
+use std::{cell::RefCell, cmp, mem, slice};
 
+/// An arena of values of type `T`, the values live as long as the arena and
+/// are dropped with it.
+///
+/// The values are stored in chunks. A chunk is a `Vec` which never grows
+/// beyond its capacity, since a resize would move the values and invalidate
+/// the references to them, a new chunk is started instead.
+///
+/// Zero-sized types are supported, they take no memory, the values are only
+/// counted to be dropped.
+///
+/// ```
+/// # use kona_memory::arena::typed_arena::TypedArena;
+/// let arena = TypedArena::new();
+/// let a = arena.alloc(String::from("a"));
+/// let bc = arena.alloc_slice([String::from("b"), String::from("c")]);
+/// a.push('!');
+/// assert_eq!((a.as_str(), bc.len()), ("a!", 2));
+/// ```
 pub struct TypedArena<T> {
     chunks: RefCell<ChunkList<T>>,
 }
 
 struct ChunkList<T> {
     current: Vec<T>,
+
+    /// The full chunks, they are kept only to own the values.
     rest: Vec<Vec<T>>,
 }
 
+/// The size of the first chunk in bytes, if the capacity is not given.
 const INITIAL_SIZE: usize = 1024;
 const MIN_CAPACITY: usize = 1;
 
+impl<T> Default for TypedArena<T> {
+    fn default() -> Self {
+        TypedArena::new()
+    }
+}
+
 impl<T> TypedArena<T> {
     pub fn new() -> TypedArena<T> {
-        // TBD: How do we handle ZST here?
+        // The capacity of a `Vec` of a zero-sized type is always `usize::MAX`,
+        // the capacity given here doesn't matter.
         let size = cmp::max(1, mem::size_of::<T>());
         TypedArena::with_capacity(INITIAL_SIZE / size)
     }
 
-    /*pub*/ fn with_capacity(n: usize) -> TypedArena<T> {
+    /// Creates an arena whose first chunk holds `n` values, or at least one.
+    /// The next chunks are larger.
+    pub fn with_capacity(n: usize) -> TypedArena<T> {
         let n = cmp::max(MIN_CAPACITY, n);
         TypedArena {
             chunks: RefCell::new(ChunkList {
@@ -36,76 +63,52 @@ impl<T> TypedArena<T> {
     }
 
     #[inline]
+    #[allow(clippy::mut_from_ref)]
     pub fn alloc(&self, value: T) -> &mut T {
-        self.alloc_fast_path(value)
-            .unwrap_or_else(|value| self.alloc_slow_path(value))
-    }
-
-    #[inline]
-    fn alloc_fast_path(&self, value: T) -> Result<&mut T, T> {
         let mut chunks = self.chunks.borrow_mut();
-        let len = chunks.current.len();
-        if len < chunks.current.capacity() {
-            // The fast path, we have enough space in the current chunk. Here
-            // `push` should not cause a resize, otherwise the references we
-            // emitted would become dangling.
-            debug_assert!(len < chunks.current.len());
-            chunks.current.push(value);
-            Ok(unsafe { &mut *chunks.current.as_mut_ptr().add(len) })
-        } else {
-            // Fast path failed, try to allocate a new chunk.
-            Err(value)
+        if chunks.current.len() == chunks.current.capacity() {
+            chunks.reserve(1);
         }
-    }
+        let len = chunks.current.len();
+        chunks.current.push(value);
+        debug_assert!(len < chunks.current.len());
 
-    fn alloc_slow_path(&self, value: T) -> &mut T {
-        &mut self.alloc_slice(iter::once(value))[0]
+        // SAFETY: The value is pushed within the capacity, the chunk is never
+        // reallocated, and nothing is removed from it until the arena is
+        // dropped. The value is not referenced by anyone else.
+        unsafe { &mut *chunks.current.as_mut_ptr().add(len) }
     }
 
+    /// Allocates the values in a contiguous slice.
+    ///
+    /// The values are collected before they are moved into the arena, so the
+    /// iterator can allocate in the same arena.
+    #[allow(clippy::mut_from_ref)]
     pub fn alloc_slice<I>(&self, iterable: I) -> &mut [T]
     where
         I: IntoIterator<Item = T>,
     {
-        let mut iter = iterable.into_iter();
+        let mut values: Vec<T> = iterable.into_iter().collect();
+        let len = values.len();
+        if len == 0 {
+            return &mut [];
+        }
 
         let mut chunks = self.chunks.borrow_mut();
-
-        let iter_min_len = iter.size_hint().0;
-        let mut next_item_index;
-        if iter_min_len > chunks.current.capacity() - chunks.current.len() {
-            chunks.reserve(iter_min_len);
-            chunks.current.extend(iter);
-            next_item_index = 0;
-        } else {
-            next_item_index = chunks.current.len();
-            let mut i = 0;
-            while let Some(elem) = iter.next() {
-                if chunks.current.len() == chunks.current.capacity() {
-                    let chunks = &mut *chunks;
-                    chunks.reserve(i + 1);
-                    let previous_chunk = chunks.rest.last_mut().unwrap();
-                    let previous_chunk_len = previous_chunk.len();
-                    chunks
-                        .current
-                        .extend(previous_chunk.drain(previous_chunk_len - i..));
-                    chunks.current.push(elem);
-                    chunks.current.extend(iter);
-                    next_item_index = 0;
-                    break;
-                } else {
-                    chunks.current.push(elem);
-                }
-                i += 1;
-            }
+        if chunks.current.capacity() - chunks.current.len() < len {
+            chunks.reserve(len);
         }
-        let new_slice_ref = &mut chunks.current[next_item_index..];
+        let start = chunks.current.len();
+        chunks.current.append(&mut values);
+        debug_assert!(start + len == chunks.current.len());
 
-        unsafe { mem::transmute::<&mut [T], &mut [T]>(new_slice_ref) }
+        // SAFETY: Like `alloc`, the values are appended within the capacity.
+        unsafe { slice::from_raw_parts_mut(chunks.current.as_mut_ptr().add(start), len) }
     }
 }
 
 impl<T> ChunkList<T> {
-    // Request a change in capacity.
+    /// Starts a new chunk which can hold at least `additional` values.
     fn reserve(&mut self, additional: usize) {
         let double_cap = self.current
             .capacity()
@@ -119,3 +122,101 @@ impl<T> ChunkList<T> {
         self.rest.push(chunk);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    /// Counts the drops of the values, to check every value is dropped once.
+    struct DropCounter<'a>(&'a Cell<usize>);
+
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_alloc_across_chunks() {
+        let arena = TypedArena::with_capacity(2);
+        let values: Vec<&mut usize> = (0..100).map(|i| arena.alloc(i)).collect();
+        let slices: Vec<&mut [usize]> = (0..20).map(|n| arena.alloc_slice(0..n)).collect();
+
+        // The references are still valid after the chunks are full.
+        for (i, value) in values.into_iter().enumerate() {
+            assert_eq!(*value, i);
+            *value = 0;
+        }
+        for (n, slice) in slices.into_iter().enumerate() {
+            assert_eq!(slice, (0..n).collect::<Vec<_>>());
+        }
+        assert_eq!(arena.alloc_slice(Vec::new()), &mut []);
+    }
+
+    #[test]
+    fn test_alloc_slice_reentrant() {
+        let arena = TypedArena::with_capacity(1);
+        let values = arena.alloc_slice((0..10).map(|i| *arena.alloc(i) * 2));
+        assert_eq!(values.iter().sum::<i32>(), 90);
+    }
+
+    #[test]
+    fn test_drop() {
+        let drops = Cell::new(0);
+        let arena = TypedArena::with_capacity(3);
+        for _ in 0..10 {
+            arena.alloc(DropCounter(&drops));
+        }
+        arena.alloc_slice((0..10).map(|_| DropCounter(&drops)));
+        assert_eq!(drops.get(), 0);
+        drop(arena);
+        assert_eq!(drops.get(), 20);
+
+        // The values can refer to each other.
+        struct Node<'a> {
+            _rc: Rc<()>,
+            next: Option<&'a Node<'a>>,
+        }
+        let rc = Rc::new(());
+        {
+            let arena = TypedArena::new();
+            let first = arena.alloc(Node { _rc: rc.clone(), next: None });
+            let second = arena.alloc(Node { _rc: rc.clone(), next: Some(first) });
+            assert!(second.next.is_some_and(|next| next.next.is_none()));
+            assert_eq!(Rc::strong_count(&rc), 3);
+        }
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    thread_local! {
+        static ZST_DROPS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// A zero-sized type with a destructor.
+    struct Zst;
+
+    impl Drop for Zst {
+        fn drop(&mut self) {
+            ZST_DROPS.with(|drops| drops.set(drops.get() + 1));
+        }
+    }
+
+    #[test]
+    fn test_zero_sized_types() {
+        let arena = TypedArena::with_capacity(0);
+        let units: Vec<&mut ()> = (0..1000).map(|_| arena.alloc(())).collect();
+        assert_eq!(units.len(), 1000);
+        assert_eq!(arena.alloc_slice([(), (), ()]).len(), 3);
+
+        let arena = TypedArena::new();
+        for _ in 0..10 {
+            arena.alloc(Zst);
+        }
+        arena.alloc_slice((0..5).map(|_| Zst));
+        assert_eq!(ZST_DROPS.with(Cell::get), 0);
+        drop(arena);
+        assert_eq!(ZST_DROPS.with(Cell::get), 15);
+    }
+}
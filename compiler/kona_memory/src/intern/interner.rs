@@ -0,0 +1,184 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The global interner behind [`Symbol`].
+//!
+//! Interning a string takes a lock, but reading the string of a symbol does
+//! not. The strings are copied into an append-only arena, and the table from
+//! the symbol ids to the strings only grows, so a string never moves once it
+//! is interned.
+
+use std::{cmp, collections::HashMap, mem, sync::{Mutex, OnceLock}};
+
+use lazy_static::lazy_static;
+
+use super::symbol::{Symbol, PRE_INTERNED};
+
+lazy_static! {
+    pub(super) static ref INTERNER: Interner = Interner::with_symbols(PRE_INTERNED);
+}
+
+pub(super) struct Interner {
+    /// The strings of the symbols, indexed by their ids.
+    strings: SymbolTable,
+
+    /// The state used to intern new strings, only one thread interns at a
+    /// time, and appends to `strings` with the lock held.
+    state: Mutex<InternerState>,
+}
+
+struct InternerState {
+    names: HashMap<&'static str, Symbol>,
+    arena: StringArena,
+}
+
+impl Interner {
+    /// Creates an interner with the given strings interned in order, so their
+    /// ids are their indices.
+    fn with_symbols(strings: &[&str]) -> Interner {
+        let interner = Interner {
+            strings: SymbolTable::new(),
+            state: Mutex::new(InternerState {
+                names: HashMap::with_capacity(strings.len()),
+                arena: StringArena::new(),
+            }),
+        };
+        for (id, string) in strings.iter().enumerate() {
+            let symbol = interner.intern(string);
+            assert_eq!(symbol.id as usize, id, "`{}` is pre-interned twice", string);
+        }
+        interner
+    }
+
+    pub(super) fn intern(&self, string: &str) -> Symbol {
+        let mut state = self.state.lock().unwrap();
+        if let Some(&symbol) = state.names.get(string) {
+            return symbol;
+        }
+
+        let id = u32::try_from(state.names.len()).expect("too many symbols");
+        let symbol = Symbol::new(id);
+        let string = state.arena.alloc(string);
+        self.strings.push(id, string);
+        state.names.insert(string, symbol);
+        symbol
+    }
+
+    pub(super) fn get(&self, symbol: Symbol) -> &'static str {
+        self.strings.get(symbol.id)
+    }
+}
+
+/// The number of entries in the first segment of a [`SymbolTable`], in bits.
+const FIRST_SEGMENT_BITS: u32 = 8;
+
+/// The number of segments to hold all `u32` ids.
+const SEGMENT_COUNT: usize = (u32::BITS - FIRST_SEGMENT_BITS + 1) as usize;
+
+/// An append-only table from the symbol ids to the strings, which can be read
+/// without locking.
+///
+/// The entries are stored in segments, segment `k` holds `256 << k` entries.
+/// A segment is allocated when the first entry in it is pushed, and never
+/// moved after that, unlike the buffer of a `Vec`.
+struct SymbolTable {
+    segments: [OnceLock<Box<[OnceLock<&'static str>]>>; SEGMENT_COUNT],
+}
+
+impl SymbolTable {
+    fn new() -> SymbolTable {
+        SymbolTable { segments: [const { OnceLock::new() }; SEGMENT_COUNT] }
+    }
+
+    /// Returns the segment and the offset in it of an id.
+    fn locate(id: u32) -> (usize, usize) {
+        let index = u64::from(id) + (1 << FIRST_SEGMENT_BITS);
+        let bits = u64::BITS - 1 - index.leading_zeros();
+        ((bits - FIRST_SEGMENT_BITS) as usize, (index - (1 << bits)) as usize)
+    }
+
+    fn push(&self, id: u32, string: &'static str) {
+        let (segment, offset) = SymbolTable::locate(id);
+        let segment = self.segments[segment].get_or_init(|| {
+            let len = 1 << (FIRST_SEGMENT_BITS as usize + segment);
+            (0..len).map(|_| OnceLock::new()).collect()
+        });
+        segment[offset].set(string).expect("the symbol is already in the table");
+    }
+
+    fn get(&self, id: u32) -> &'static str {
+        let (segment, offset) = SymbolTable::locate(id);
+        self.segments[segment]
+            .get()
+            .and_then(|segment| segment[offset].get())
+            .expect("the symbol is not interned")
+    }
+}
+
+/// The minimum size of a chunk of a [`StringArena`] in bytes.
+const CHUNK_SIZE: usize = 4096;
+
+/// An append-only arena of strings.
+///
+/// The strings are copied into chunks. A chunk never grows beyond its
+/// capacity, a new chunk is started instead, so the strings in it are never
+/// moved. The arena lives in the global interner and is never dropped, so the
+/// strings are `'static`.
+struct StringArena {
+    current: String,
+
+    /// The full chunks, they are kept only to own the strings.
+    full: Vec<String>,
+}
+
+impl StringArena {
+    fn new() -> StringArena {
+        StringArena { current: String::with_capacity(CHUNK_SIZE), full: Vec::new() }
+    }
+
+    fn alloc(&mut self, string: &str) -> &'static str {
+        if self.current.capacity() - self.current.len() < string.len() {
+            let chunk = String::with_capacity(cmp::max(CHUNK_SIZE, string.len()));
+            self.full.push(mem::replace(&mut self.current, chunk));
+        }
+        let start = self.current.len();
+        self.current.push_str(string);
+
+        // SAFETY: There is enough capacity, `push_str` doesn't reallocate the
+        // chunk, and nothing is removed from it. The chunk is never freed,
+        // since the arena is never dropped.
+        unsafe { &*(&self.current[start..] as *const str) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_table_locate() {
+        assert_eq!(SymbolTable::locate(0), (0, 0));
+        assert_eq!(SymbolTable::locate(255), (0, 255));
+        assert_eq!(SymbolTable::locate(256), (1, 0));
+        assert_eq!(SymbolTable::locate(767), (1, 511));
+        assert_eq!(SymbolTable::locate(768), (2, 0));
+        assert_eq!(SymbolTable::locate(u32::MAX), (SEGMENT_COUNT - 1, 255));
+    }
+
+    #[test]
+    fn test_interner() {
+        let interner: &'static Interner = Box::leak(Box::new(Interner::with_symbols(&["a", ""])));
+        assert_eq!(interner.intern(""), Symbol::new(1));
+
+        // Fill the first segment and the first chunk, the strings don't move.
+        let long = "x".repeat(CHUNK_SIZE + 1);
+        let first = interner.get(interner.intern("first"));
+        let symbols: Vec<_> = (0..1000).map(|i| interner.intern(&i.to_string())).collect();
+        let long_symbol = interner.intern(&long);
+        assert_eq!(interner.get(interner.intern("first")).as_ptr(), first.as_ptr());
+        for (i, symbol) in symbols.iter().enumerate() {
+            assert_eq!(interner.get(*symbol), i.to_string());
+        }
+        assert_eq!(interner.get(long_symbol), long);
+    }
+}
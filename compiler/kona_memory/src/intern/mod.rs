@@ -1,4 +1,5 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
+mod interner;
 pub mod symbol;
@@ -1,74 +1,147 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::fmt;
 
+use super::interner::INTERNER;
+
+/// An interned string. Symbols are compared by their ids, and their strings
+/// are read without locking the interner.
+///
+/// The keywords and the names of the built-in types are interned in advance,
+/// and are available as constants in [`kw`] and [`sym`], e.g. `sym::int`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Symbol {
     pub(crate) id: u32,
 }
 
 impl Symbol {
-    pub(crate) fn new(id: u32) -> Symbol {
+    pub(crate) const fn new(id: u32) -> Symbol {
         Symbol { id }
     }
 
+    /// Interns the string, equal strings are interned as the same symbol.
     pub fn intern(string: &str) -> Symbol {
-        GLOBAL_INTERNER.lock().unwrap().intern(string)
+        INTERNER.intern(string)
     }
 
-    pub fn as_str(&self) -> &str {
-        // SAFETY: The lifetime of the return value is the same as `&self`, but
-        // actually tied to the lifetime of the underlying interner. Interners
-        // are long-lived, this cast is safe.
-        unsafe {
-            std::mem::transmute::<&str, &str>(
-                GLOBAL_INTERNER.lock().unwrap().get(*self),
-            )
-        }
+    /// Returns the interned string, it lives as long as the program.
+    pub fn as_str(&self) -> &'static str {
+        INTERNER.get(*self)
     }
+}
 
-    pub fn to_string(&self) -> String {
-        self.as_str().to_string()
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
-use lazy_static::lazy_static;
+/// Defines the pre-interned symbols, the constants in [`kw`] and [`sym`] and
+/// their strings in [`PRE_INTERNED`], in the order of their ids.
+macro_rules! symbols {
+    (
+        Keywords { $($kw:ident: $kw_str:literal,)* }
+        Symbols { $($sym:ident: $sym_str:literal,)* }
+    ) => {
+        #[allow(non_camel_case_types)]
+        #[repr(u32)]
+        enum PreInterned {
+            $($kw,)*
+            $($sym,)*
+        }
 
-lazy_static! {
-    static ref GLOBAL_INTERNER: Mutex<Interner> =
-        Mutex::new(Interner::default());
-}
+        /// The strings of the pre-interned symbols, they are interned first,
+        /// so their ids are their indices.
+        pub(crate) const PRE_INTERNED: &[&str] = &[$($kw_str,)* $($sym_str,)*];
 
-#[derive(Default)]
-struct Interner {
-    storage: Vec<String>,
-    names: HashMap<&'static str, Symbol>,
-}
+        /// The keywords, the boolean literals and the empty identifier.
+        #[allow(non_upper_case_globals)]
+        pub mod kw {
+            use super::{PreInterned, Symbol};
+
+            $(pub const $kw: Symbol = Symbol::new(PreInterned::$kw as u32);)*
+        }
 
+        /// The names of the built-in types.
+        #[allow(non_upper_case_globals)]
+        pub mod sym {
+            use super::{PreInterned, Symbol};
 
-impl Interner {
-    fn intern(&mut self, string: &str) -> Symbol {
-        if let Some(&symbol) = self.names.get(string) {
-            return symbol;
+            $(pub const $sym: Symbol = Symbol::new(PreInterned::$sym as u32);)*
         }
+    };
+}
 
-        let symbol = Symbol::new(self.storage.len() as u32);
-        self.storage.push(string.to_string());
+symbols! {
+    Keywords {
+        // The name of an identifier missing at a syntax error.
+        Empty: "",
+        Else: "else",
+        Fn: "fn",
+        If: "if",
+        In: "in",
+        Infix: "infix",
+        Infixl: "infixl",
+        Infixr: "infixr",
+        Let: "let",
+        Then: "then",
+        True: "true",
+        False: "false",
+    }
+
+    Symbols {
+        arrow: "->",
+        bool: "bool",
+        char: "char",
+        float: "float",
+        int: "int",
+        string: "string",
+        unit: "unit",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
 
-        // SAFETY: We put the string into the interner, and never remove item
-        // from it. It is safe to use `&'static str` here.
-        let str: &'static str = unsafe {
-            let str: &str = self.storage.last().unwrap().as_str();
-            std::mem::transmute::<&str, &'static str>(str)
-        };
+    use super::*;
 
-        self.names.insert(str, symbol);
-        symbol
+    #[test]
+    fn test_intern() {
+        let a = Symbol::intern("test_intern_a");
+        assert_eq!(Symbol::intern("test_intern_a"), a);
+        assert_ne!(Symbol::intern("test_intern_b"), a);
+        assert_eq!(a.as_str(), "test_intern_a");
+        assert_eq!(a.to_string(), "test_intern_a");
     }
 
-    fn get(&self, symbol: Symbol) -> &str {
-        &self.storage[symbol.id as usize]
+    #[test]
+    fn test_pre_interned() {
+        assert_eq!(kw::Empty.as_str(), "");
+        assert_eq!(kw::Let.as_str(), "let");
+        assert_eq!(Symbol::intern("false"), kw::False);
+        assert_eq!(sym::arrow.as_str(), "->");
+        assert_eq!(Symbol::intern("int"), sym::int);
+    }
+
+    #[test]
+    fn test_intern_from_threads() {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    (0..500)
+                        .map(|i| Symbol::intern(&format!("test_thread_{}", i)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let results: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+        for symbols in &results {
+            assert_eq!(symbols, &results[0]);
+        }
+        for (i, symbol) in results[0].iter().enumerate() {
+            assert_eq!(symbol.as_str(), format!("test_thread_{}", i));
+        }
     }
 }
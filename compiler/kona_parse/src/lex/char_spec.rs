@@ -1,6 +1,8 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
+use unicode_general_category::{get_general_category, GeneralCategory};
+
 /// Returns true if the character is a inline space, space (U+0020) or
 /// horizontal tab (U+0009).
 pub fn is_inline_space(c: char) -> bool {
@@ -32,14 +34,18 @@ pub fn is_line_break(c: char) -> bool {
               | '\x0D'/* U+000D carriage return */)
 }
 
-/// Returns true if the character can be the head of an identifier.
+/// Returns true if the character can be the head of an identifier, i.e. an
+/// `XID_Start` character or `_`, see [UAX #31].
+///
+/// [UAX #31]: https://www.unicode.org/reports/tr31/
 pub fn is_ident_head(c: char) -> bool {
-    matches!(c, 'a'..='z' | 'A'..='Z' | '_')
+    c == '_' || unicode_ident::is_xid_start(c)
 }
 
-/// Returns true if the character can be a part of an identifier.
+/// Returns true if the character can be a part of an identifier, i.e. an
+/// `XID_Continue` character, which includes `_` and the decimal digits.
 pub fn is_ident_part(c: char) -> bool {
-    matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_')
+    unicode_ident::is_xid_continue(c)
 }
 
 /// Returns true if the character can be a part of an operator, including `!`,
@@ -50,7 +56,84 @@ pub fn is_operator_part(c: char) -> bool {
               | ':' | '<' | '=' | '>' | '?' | '^' | '|' | '~')
 }
 
+/// Returns true if the character can be a part of an operator except the
+/// first one, i.e. an operator character or `.`, e.g. `+.`.
+pub fn is_operator_tail(c: char) -> bool {
+    is_operator_part(c) || c == '.'
+}
+
 /// Returns true if the character is a decimal digit.
 pub fn is_digit(c: char) -> bool {
-    matches!(c, '0'..='9')
+    c.is_ascii_digit()
+}
+
+/// The names of the characters which can't start a token, but are easily
+/// typed by mistake, e.g. copied from a rich text editor.
+const CHAR_NAMES: &[(char, &str)] = &[
+    ('\0', "NULL"),
+    ('\x0B', "LINE TABULATION"),
+    ('\x0C', "FORM FEED"),
+    ('#', "NUMBER SIGN"),
+    (',', "COMMA"),
+    ('.', "FULL STOP"),
+    ('@', "COMMERCIAL AT"),
+    ('[', "LEFT SQUARE BRACKET"),
+    ('\\', "REVERSE SOLIDUS"),
+    (']', "RIGHT SQUARE BRACKET"),
+    ('`', "GRAVE ACCENT"),
+    ('{', "LEFT CURLY BRACKET"),
+    ('}', "RIGHT CURLY BRACKET"),
+    ('\u{A0}', "NO-BREAK SPACE"),
+    ('\u{AD}', "SOFT HYPHEN"),
+    ('\u{D7}', "MULTIPLICATION SIGN"),
+    ('\u{200B}', "ZERO WIDTH SPACE"),
+    ('\u{200C}', "ZERO WIDTH NON-JOINER"),
+    ('\u{200D}', "ZERO WIDTH JOINER"),
+    ('\u{2013}', "EN DASH"),
+    ('\u{2014}', "EM DASH"),
+    ('\u{2018}', "LEFT SINGLE QUOTATION MARK"),
+    ('\u{2019}', "RIGHT SINGLE QUOTATION MARK"),
+    ('\u{201C}', "LEFT DOUBLE QUOTATION MARK"),
+    ('\u{201D}', "RIGHT DOUBLE QUOTATION MARK"),
+    ('\u{2028}', "LINE SEPARATOR"),
+    ('\u{2029}', "PARAGRAPH SEPARATOR"),
+    ('\u{2212}', "MINUS SIGN"),
+    ('\u{3000}', "IDEOGRAPHIC SPACE"),
+    ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+];
+
+/// Describes a character by its code point and name for diagnostics, e.g.
+/// `U+00A0 NO-BREAK SPACE`. A character without a known name is described by
+/// its general category, e.g. `U+1F600 (other symbol)`.
+pub fn describe_char(c: char) -> String {
+    match CHAR_NAMES.iter().find(|(known, _)| *known == c) {
+        Some((_, name)) => format!("U+{:04X} {}", c as u32, name),
+        None => format!("U+{:04X} ({})", c as u32, category_name(get_general_category(c))),
+    }
+}
+
+/// Returns true if the character is invisible or looks like a space, so it
+/// can't be quoted in a diagnostic message.
+pub fn is_invisible(c: char) -> bool {
+    c.is_whitespace() || matches!(
+        get_general_category(c),
+        GeneralCategory::Control | GeneralCategory::Format
+            | GeneralCategory::SpaceSeparator | GeneralCategory::LineSeparator
+            | GeneralCategory::ParagraphSeparator | GeneralCategory::Unassigned
+            | GeneralCategory::PrivateUse | GeneralCategory::Surrogate
+            | GeneralCategory::NonspacingMark | GeneralCategory::EnclosingMark
+    )
+}
+
+/// Returns the name of a general category in lowercase words, e.g. `other
+/// symbol` for `GeneralCategory::OtherSymbol`.
+fn category_name(category: GeneralCategory) -> String {
+    let mut name = String::new();
+    for c in format!("{:?}", category).chars() {
+        if c.is_ascii_uppercase() && !name.is_empty() {
+            name.push(' ');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
 }
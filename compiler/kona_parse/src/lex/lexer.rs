@@ -2,14 +2,26 @@
 // root for license information.
 
 use std::collections::HashMap;
-use kona_source::pos::Pos;
+use kona_diagnostic::Diagnostic;
+use kona_source::{pos::Pos, span::Span};
 
 use super::token::{TokenKind, LitKind, Token, TriviaKind};
 use super::source_iter::SourceIter;
 use super::char_spec::*;
 
-/// Creates an iterator that produces tokens from the input string.
+/// Creates an iterator that produces tokens from the input string, the trivia
+/// (whitespace, line breaks and comments) is skipped.
 pub fn tokenize(input: &str, start_pos: Pos) -> impl Iterator<Item = Token> + '_ {
+    tokenize_with_trivia(input, start_pos)
+        .filter(|token| !matches!(token.kind, TokenKind::Trivia(_)))
+}
+
+/// Like [`tokenize`], but keeps the trivia tokens, every byte of the input
+/// belongs to a token.
+///
+/// The tokens may be invalid, e.g. an unterminated string literal, the errors
+/// are not reported, see [`token_error`].
+pub fn tokenize_with_trivia(input: &str, start_pos: Pos) -> impl Iterator<Item = Token> + '_ {
     let mut iter = SourceIter::new(input, start_pos);
     std::iter::from_fn(move || {
         if iter.is_eof() {
@@ -18,9 +30,50 @@ pub fn tokenize(input: &str, start_pos: Pos) -> impl Iterator<Item = Token> + '_
             Some(iter.lex_token())
         }
     })
+}
 
-    // Trivia is not supported yet.
-    .filter(|token| !matches!(token.kind, TokenKind::Trivia(_)))
+/// Returns the error in a token, e.g. an unterminated string literal or an
+/// invalid character, or `None` if the token is valid. `text` is the source
+/// text of the token.
+pub fn token_error(token: &Token, text: &str) -> Option<Diagnostic> {
+    let opening = |len: usize| Span::new(token.span.start, token.span.start + len);
+    let diagnostic = match token.kind {
+        TokenKind::Lit(LitKind::String { terminated: false }) => {
+            Diagnostic::error("unterminated string literal")
+                .with_primary(opening(1), "the string literal starts here")
+                .with_note("the string literal is not closed before the end of file")
+        }
+        TokenKind::Lit(LitKind::Char { terminated: false }) => {
+            Diagnostic::error("unterminated character literal")
+                .with_primary(opening(1), "the character literal starts here")
+                .with_note("the character literal is not closed before the end of line")
+        }
+        TokenKind::Trivia(
+            TriviaKind::MultiLineComment { terminated: false }
+            | TriviaKind::MultiLineDocComment { terminated: false },
+        ) => {
+            let diagnostic = Diagnostic::error("unterminated block comment")
+                .with_primary(opening(2), "the block comment starts here");
+            if text[2..].contains("/-") {
+                diagnostic.with_help(
+                    "block comments can be nested, every `/-` needs a matching `-/`",
+                )
+            } else {
+                diagnostic
+            }
+        }
+        TokenKind::Invalid => {
+            let c = text.chars().next()?;
+            let message = if is_invisible(c) {
+                format!("unexpected character {}", describe_char(c))
+            } else {
+                format!("unexpected character `{}` ({})", c, describe_char(c))
+            };
+            Diagnostic::error(message).with_primary(token.span, "")
+        }
+        _ => return None,
+    };
+    Some(diagnostic)
 }
 
 lazy_static! {
@@ -30,6 +83,8 @@ lazy_static! {
         ("if", TokenKind::If),
         ("in", TokenKind::In),
         ("infix", TokenKind::Infix),
+        ("infixl", TokenKind::Infixl),
+        ("infixr", TokenKind::Infixr),
         ("let", TokenKind::Let),
         ("then", TokenKind::Then),
         ("true", TokenKind::Lit(LitKind::Bool)),
@@ -50,15 +105,11 @@ lazy_static! {
 impl SourceIter<'_> {
     pub(super) fn lex_token(&mut self) -> Token {
         let kind = match self.peek_fst() {
-            // Comment or operator start with '/'.
-            '/' => {
-                match self.peek_snd() {
-                    '-' => self.lex_block_comment(), // Multi-line comment.
-                    '/' => self.lex_line_comment(),  // Single-line comment.
-                    _ => self.lex_operator(),
-                }
-            }
+            // Multi-line comment or operator start with '/'.
+            '/' if self.peek_snd() == '-' => self.lex_block_comment(),
 
+            // Operator, or single-line comment starting with dashes, see
+            // `lex_operator`.
             c if is_operator_part(c) => self.lex_operator(),
 
             // Whitespace sequence.
@@ -79,6 +130,9 @@ impl SourceIter<'_> {
             // String literal.
             '"' => self.lex_string(),
 
+            // Character literal.
+            '\'' => self.lex_char(),
+
             ';' => { self.eat(); TokenKind::Semi }
             '(' => { self.eat(); TokenKind::LParen }
             ')' => { self.eat(); TokenKind::RParen }
@@ -88,14 +142,25 @@ impl SourceIter<'_> {
         Token::new(kind, self.consume_span())
     }
 
-    fn lex_line_comment(&mut self) -> TokenKind {
-        debug_assert!(self.eat() == '/' && self.eat() == '/');
-        self.eat_while(|c| c != '\n');
-        TokenKind::Trivia(TriviaKind::SingleLineComment)
+    /// Lexes the rest of a single-line comment, after the leading dashes.
+    fn lex_line_comment(&mut self, is_doc: bool) -> TokenKind {
+        self.eat_while(|c| !is_line_break(c));
+        if is_doc {
+            TokenKind::Trivia(TriviaKind::SingleLineDocComment)
+        } else {
+            TokenKind::Trivia(TriviaKind::SingleLineComment)
+        }
     }
 
+    /// Lexes a multi-line comment, `/- ... -/`, or a doc comment `/-- ... -/`.
     fn lex_block_comment(&mut self) -> TokenKind {
-        debug_assert!(self.eat() == '/' && self.eat() == '-');
+        debug_assert!(self.peek_fst() == '/' && self.peek_snd() == '-');
+        self.eat();
+        self.eat();
+
+        // `/--/` is an empty comment, and `/---...` is usually a separator
+        // line, neither is a doc comment.
+        let is_doc = self.peek_fst() == '-' && !matches!(self.peek_snd(), '-' | '/');
 
         let mut depth = 1;
         while let Some(c) = self.next() {
@@ -108,9 +173,7 @@ impl SourceIter<'_> {
                     self.eat();
                     depth -= 1;
                     if depth == 0 {
-                        return TokenKind::Trivia(TriviaKind::MultiLineComment {
-                            terminated: true,
-                        });
+                        return block_comment_kind(is_doc, true);
                     }
                 }
                 _ => (),
@@ -118,13 +181,11 @@ impl SourceIter<'_> {
         }
 
         // Unterminated multi-line comment.
-        TokenKind::Trivia(TriviaKind::MultiLineComment {
-            terminated: false,
-        })
+        block_comment_kind(is_doc, false)
     }
 
     fn lex_inline_spaces(&mut self) -> TokenKind {
-        debug_assert!(is_inline_space(self.eat()));
+        debug_assert!(is_inline_space(self.peek_fst()));
 
         self.eat_while(is_inline_space);
         TokenKind::Trivia(TriviaKind::Whitespace)
@@ -154,36 +215,72 @@ impl SourceIter<'_> {
             .unwrap_or(TokenKind::Ident)
     }
 
+    /// Lexes an operator, or a single-line comment.
+    ///
+    /// A sequence of two or more dashes starts a single-line comment, unless
+    /// it is a part of a longer operator, e.g. `-->` or `<--` are operators.
+    /// Exactly three dashes start a doc comment, e.g. `--- Returns x.`.
     fn lex_operator(&mut self) -> TokenKind {
         debug_assert!(is_operator_part(self.peek_fst()));
 
-        let mut ident = String::new();
-        while is_operator_part(self.peek_fst()) {
+        let mut ident = String::from(self.eat());
+        while is_operator_tail(self.peek_fst()) {
             ident.push(self.eat());
         }
 
+        if ident.len() >= 2 && ident.chars().all(|c| c == '-') {
+            return self.lex_line_comment(ident.len() == 3);
+        }
+
         OP_LIKE_PUNCT_TABLE
             .get(ident.as_str())
             .cloned()
             .unwrap_or(TokenKind::Op)
     }
 
+    /// Lexes a number literal, e.g. `42`, `0xFF`, `1_000` or `6.02e23`.
+    ///
+    /// Only the extent of the literal is found here, the letters and digits
+    /// right after it are part of it, e.g. `0b12` or `12px`. The literal is
+    /// checked when it is decoded, see [`decode_lit`].
+    ///
+    /// [`decode_lit`]: crate::parse::lit::decode_lit
     fn lex_number(&mut self) -> TokenKind {
-        debug_assert!(matches!(self.eat(), '0'..='9'));
+        debug_assert!(is_digit(self.peek_fst()));
+
+        if self.peek_fst() == '0' && matches!(self.peek_snd(), 'x' | 'o' | 'b') {
+            self.eat(); // Eat '0'.
+            self.eat(); // Eat the base prefix.
+            self.eat_while(is_ident_part);
+            return TokenKind::Lit(LitKind::Int);
+        }
 
-        self.eat_while(is_digit);
+        let is_digit_part = |c: char| is_digit(c) || c == '_';
+        let mut kind = LitKind::Int;
+        self.eat_while(is_digit_part);
 
         if self.peek_fst() == '.' && is_digit(self.peek_snd()) {
             self.eat(); // Eat '.'.
-            self.eat_while(is_digit);
-            TokenKind::Lit(LitKind::Float)
-        } else {
-            TokenKind::Lit(LitKind::Int)
+            self.eat_while(is_digit_part);
+            kind = LitKind::Float;
         }
+        if matches!(self.peek_fst(), 'e' | 'E') {
+            self.eat();
+            if matches!(self.peek_fst(), '+' | '-') {
+                self.eat();
+            }
+            self.eat_while(is_digit_part);
+            kind = LitKind::Float;
+        }
+
+        // A suffix, which is reported when the literal is decoded.
+        self.eat_while(is_ident_part);
+        TokenKind::Lit(kind)
     }
 
     fn lex_string(&mut self) -> TokenKind {
-        debug_assert!(self.eat() == '"');
+        debug_assert!(self.peek_fst() == '"');
+        self.eat();
 
         while let Some(c) = self.next() {
             match c {
@@ -205,4 +302,88 @@ impl SourceIter<'_> {
             terminated: false,
         })
     }
+
+    /// Lexes a character literal, e.g. `'a'` or `'\''`. Any number of
+    /// characters are taken up to the closing `'` on the same line, and
+    /// checked when the literal is decoded.
+    fn lex_char(&mut self) -> TokenKind {
+        debug_assert!(self.peek_fst() == '\'');
+        self.eat();
+
+        loop {
+            match self.peek_fst() {
+                _ if self.is_eof() => break,
+                c if is_line_break(c) => break,
+                '\'' => {
+                    self.eat();
+                    return TokenKind::Lit(LitKind::Char { terminated: true });
+                }
+                '\\' => {
+                    self.eat();
+                    if !is_line_break(self.peek_fst()) {
+                        self.eat();
+                    }
+                }
+                _ => {
+                    self.eat();
+                }
+            }
+        }
+
+        // Unterminated character literal.
+        TokenKind::Lit(LitKind::Char { terminated: false })
+    }
+}
+
+fn block_comment_kind(is_doc: bool, terminated: bool) -> TokenKind {
+    if is_doc {
+        TokenKind::Trivia(TriviaKind::MultiLineDocComment { terminated })
+    } else {
+        TokenKind::Trivia(TriviaKind::MultiLineComment { terminated })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lexes the source, returns the error messages and the spans of their
+    /// primary labels.
+    fn lex_errors(src: &str) -> Vec<(String, usize, usize)> {
+        tokenize_with_trivia(src, Pos::from_usize(0))
+            .filter_map(|token| {
+                let text = &src[token.span.start.to_usize()..token.span.end.to_usize()];
+                token_error(&token, text)
+            })
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().unwrap();
+                (diagnostic.message, span.start.to_usize(), span.end.to_usize())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lex_errors() {
+        assert_eq!(lex_errors("let s = \"abc\" in s /- -/"), []);
+        assert_eq!(
+            lex_errors("f \"abc\nx"),
+            [("unterminated string literal".to_string(), 2, 3)],
+        );
+        assert_eq!(
+            lex_errors("c 'a' '\\'' 'b\nx"),
+            [("unterminated character literal".to_string(), 11, 12)],
+        );
+        assert_eq!(
+            lex_errors("x /- a /- b -/\n"),
+            [("unterminated block comment".to_string(), 2, 4)],
+        );
+        assert_eq!(
+            lex_errors("a\u{A0}b @ \u{1F600}"),
+            [
+                ("unexpected character U+00A0 NO-BREAK SPACE".to_string(), 1, 3),
+                ("unexpected character `@` (U+0040 COMMERCIAL AT)".to_string(), 5, 6),
+                ("unexpected character `\u{1F600}` (U+1F600 (other symbol))".to_string(), 7, 11),
+            ],
+        );
+    }
 }
@@ -7,4 +7,4 @@ pub mod token;
 pub mod lexer;
 pub mod token_iter;
 
-pub use lexer::tokenize;
+pub use lexer::{token_error, tokenize, tokenize_with_trivia};
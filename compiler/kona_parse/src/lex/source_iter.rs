@@ -3,7 +3,6 @@
 
 use std::str::Chars;
 
-use kona_diagnostic::DiagnosticsEngine;
 use kona_source::{pos::Pos, span::Span};
 
 /// Peekable iterator with position information over a source string.
@@ -58,7 +57,7 @@ impl<'s> Iterator for SourceIter<'s> {
 const EOF_CHAR: char = '\0';
 
 impl<'s> SourceIter<'s> {
-    pub(crate) fn new(source: &'s str, start_pos: Pos) -> SourceIter {
+    pub(crate) fn new(source: &'s str, start_pos: Pos) -> SourceIter<'s> {
         SourceIter {
             start_pos,
             initial_len: source.len(),
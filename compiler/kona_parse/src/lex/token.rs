@@ -2,6 +2,7 @@
 // root for license information.
 
 use kona_source::span::Span;
+use kona_syntax::kind::SyntaxKind;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Token {
@@ -28,6 +29,8 @@ pub enum TokenKind {
     If,
     In,
     Infix,
+    Infixl,
+    Infixr,
     Let,
     Then,
 
@@ -41,18 +44,85 @@ pub enum TokenKind {
     Eof,
 }
 
+impl TokenKind {
+    /// Returns the kind of the token in the concrete syntax tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics on [`TokenKind::Eof`], which is not a part of the source.
+    pub fn syntax_kind(&self) -> SyntaxKind {
+        match self {
+            TokenKind::LParen => SyntaxKind::LParen,
+            TokenKind::RParen => SyntaxKind::RParen,
+            TokenKind::Semi => SyntaxKind::Semi,
+            TokenKind::Eq => SyntaxKind::Eq,
+            TokenKind::DArrow => SyntaxKind::DArrow,
+            TokenKind::Else => SyntaxKind::ElseKw,
+            TokenKind::Fn => SyntaxKind::FnKw,
+            TokenKind::If => SyntaxKind::IfKw,
+            TokenKind::In => SyntaxKind::InKw,
+            TokenKind::Infix => SyntaxKind::InfixKw,
+            TokenKind::Infixl => SyntaxKind::InfixlKw,
+            TokenKind::Infixr => SyntaxKind::InfixrKw,
+            TokenKind::Let => SyntaxKind::LetKw,
+            TokenKind::Then => SyntaxKind::ThenKw,
+            TokenKind::Ident => SyntaxKind::Ident,
+            TokenKind::Op => SyntaxKind::Op,
+            TokenKind::Lit(LitKind::Int) => SyntaxKind::IntLit,
+            TokenKind::Lit(LitKind::Float) => SyntaxKind::FloatLit,
+            TokenKind::Lit(LitKind::Bool) => SyntaxKind::BoolLit,
+            TokenKind::Lit(LitKind::String { .. }) => SyntaxKind::StringLit,
+            TokenKind::Lit(LitKind::Char { .. }) => SyntaxKind::CharLit,
+            TokenKind::Trivia(TriviaKind::Whitespace) => SyntaxKind::Whitespace,
+            TokenKind::Trivia(TriviaKind::Eol) => SyntaxKind::Eol,
+            TokenKind::Trivia(TriviaKind::SingleLineComment) => SyntaxKind::LineComment,
+            TokenKind::Trivia(TriviaKind::MultiLineComment { .. }) => SyntaxKind::BlockComment,
+            TokenKind::Trivia(TriviaKind::SingleLineDocComment) => SyntaxKind::LineDocComment,
+            TokenKind::Trivia(TriviaKind::MultiLineDocComment { .. }) => {
+                SyntaxKind::BlockDocComment
+            }
+            TokenKind::Invalid => SyntaxKind::Invalid,
+            TokenKind::Eof => panic!("end of file is not a token in the syntax tree"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum LitKind {
     Int,
     Float,
     Bool,
     String { terminated: bool },
+
+    /// `'a'`, the character literal is terminated at the end of line.
+    Char { terminated: bool },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TriviaKind {
+    /// `-- ...`
     SingleLineComment,
+
+    /// `/- ... -/`
     MultiLineComment { terminated: bool },
+
+    /// `--- ...`, the documentation of the following declaration.
+    SingleLineDocComment,
+
+    /// `/-- ... -/`, the documentation of the following declaration.
+    MultiLineDocComment { terminated: bool },
     Whitespace,
     Eol,
 }
+
+impl TriviaKind {
+    /// Returns true for all kinds of comments, including doc comments.
+    pub fn is_comment(&self) -> bool {
+        !matches!(self, TriviaKind::Whitespace | TriviaKind::Eol)
+    }
+
+    /// Returns true for the comments which end at the end of line.
+    pub fn is_line_comment(&self) -> bool {
+        matches!(self, TriviaKind::SingleLineComment | TriviaKind::SingleLineDocComment)
+    }
+}
@@ -1,22 +1,53 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
+use kona_diagnostic::DiagnosticsEngine;
 use kona_source::pos::Pos;
 
-use super::{token::{Token, TokenKind}, source_iter::SourceIter, tokenize};
+use super::{lexer::token_error, token::{Token, TokenKind}, source_iter::SourceIter};
 
 /// Peekable iterator of token stream.
+///
+/// The trivia is skipped, but kept in a buffer until it is taken by
+/// [`take_trivia`](TokenIter::take_trivia), so the parser can put it in the
+/// concrete syntax tree. The invalid characters are skipped like trivia too,
+/// after they are reported, so the parser doesn't report them again and goes
+/// on as if they were not there.
 pub struct TokenIter<'src> {
     iter: SourceIter<'src>,
     peeked_token: Option<Token>,
+
+    /// The trivia and the invalid characters skipped before the peeked token.
+    trivia: Vec<Token>,
+
+    input: &'src str,
+    start_pos: Pos,
+
+    /// Where the errors in the tokens are reported, e.g. an unterminated
+    /// string literal. The errors are ignored if it is `None`.
+    diag: Option<&'src DiagnosticsEngine>,
 }
 
 impl<'src> TokenIter<'src> {
     /// Creates a new token iterator from the input string.
-    pub fn new(input: &str, start_pos: Pos) -> TokenIter {
-        let start_pos = start_pos.into();
+    pub fn new(input: &str, start_pos: Pos) -> TokenIter<'_> {
         let iter = SourceIter::new(input, start_pos);
-        TokenIter { iter, peeked_token: None }
+        TokenIter {
+            iter, input, start_pos,
+            peeked_token: None,
+            trivia: Vec::new(),
+            diag: None,
+        }
+    }
+
+    /// Creates a new token iterator which reports the errors in the tokens to
+    /// `diag`, each error is reported once when the token is lexed.
+    pub fn with_diagnostics(
+        input: &'src str,
+        start_pos: Pos,
+        diag: &'src DiagnosticsEngine,
+    ) -> TokenIter<'src> {
+        TokenIter { diag: Some(diag), ..TokenIter::new(input, start_pos) }
     }
 
     pub fn peek(&mut self) -> Option<Token> {
@@ -30,15 +61,32 @@ impl<'src> TokenIter<'src> {
             }
 
             let next = self.iter.lex_token();
-            if !matches!(next.kind, TokenKind::Trivia(_)) {
+            self.report_error(&next);
+            if !matches!(next.kind, TokenKind::Trivia(_) | TokenKind::Invalid) {
                 self.peeked_token = Some(next.clone());
                 return Some(next)
             }
+            self.trivia.push(next);
         };
     }
 
     pub fn eat(&mut self) -> Option<Token> {
         self.peek();
-        return std::mem::replace(&mut self.peeked_token, None);
+        self.peeked_token.take()
+    }
+
+    fn report_error(&self, token: &Token) {
+        let Some(diag) = self.diag else { return };
+        let start = token.span.start.to_usize() - self.start_pos.to_usize();
+        let end = token.span.end.to_usize() - self.start_pos.to_usize();
+        if let Some(diagnostic) = token_error(token, &self.input[start..end]) {
+            diag.emit(diagnostic);
+        }
+    }
+
+    /// Returns the trivia and the invalid characters skipped since the last
+    /// call, i.e. the ones before the peeked token, or before the end of file.
+    pub fn take_trivia(&mut self) -> Vec<Token> {
+        std::mem::take(&mut self.trivia)
     }
 }
@@ -0,0 +1,233 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Fixity resolution, the pass that rewrites [`ExprKind::PendingSeq`]s into
+//! nested [`ExprKind::InfixOp`]s.
+//!
+//! The parser doesn't know the fixity of operators, since fixities can be
+//! declared anywhere with `infix`, `infixl` and `infixr`. It parses every infix
+//! expression as a flat sequence, and leaves the rest to this pass.
+
+use std::{collections::HashMap, mem};
+
+use kona_ast::expr::{Expr, ExprKind, operator::{Assoc, Fixity, Operator}};
+use kona_diagnostic::{Diagnostic, DiagnosticsEngine};
+use kona_memory::intern::symbol::Symbol;
+use kona_source::span::Span;
+
+/// The default fixities of the built-in operators, they are in effect in every
+/// file. Most of them follow the initial fixity of Standard ML.
+pub const PRELUDE_FIXITIES: &[(&str, Assoc, u8)] = &[
+    ("*",  Assoc::Left,  7),
+    ("/",  Assoc::Left,  7),
+    ("%",  Assoc::Left,  7),
+    ("*.", Assoc::Left,  7),
+    ("/.", Assoc::Left,  7),
+    ("+",  Assoc::Left,  6),
+    ("-",  Assoc::Left,  6),
+    ("+.", Assoc::Left,  6),
+    ("-.", Assoc::Left,  6),
+    ("^",  Assoc::Left,  6),
+    ("::", Assoc::Right, 5),
+    ("=",  Assoc::None,  4),
+    ("<>", Assoc::None,  4),
+    ("<",  Assoc::None,  4),
+    (">",  Assoc::None,  4),
+    ("<=", Assoc::None,  4),
+    (">=", Assoc::None,  4),
+    ("&&", Assoc::Right, 3),
+    ("||", Assoc::Right, 2),
+];
+
+/// Resolves all infix expressions in `expr` in place.
+///
+/// Every precedence conflict is reported to `diag`, the conflicting operators
+/// are then treated as left associative so that the pass can go on.
+pub fn resolve_fixity(expr: &mut Expr, diag: &DiagnosticsEngine) {
+    FixityResolver::new(diag).resolve_expr(expr);
+}
+
+/// A lexically scoped fixity table.
+pub struct FixityEnv {
+    scopes: Vec<HashMap<Symbol, Fixity>>,
+}
+
+impl FixityEnv {
+    /// Creates a fixity table with the [`PRELUDE_FIXITIES`] in it.
+    pub fn with_prelude() -> FixityEnv {
+        let prelude = PRELUDE_FIXITIES
+            .iter()
+            .map(|&(op, assoc, prec)| {
+                (Symbol::intern(op), Fixity::new(assoc, prec))
+            })
+            .collect();
+        FixityEnv { scopes: vec![prelude] }
+    }
+
+    /// Looks up the fixity of an operator, the innermost declaration wins.
+    pub fn lookup(&self, op: Symbol) -> Fixity {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&op).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        debug_assert!(self.scopes.len() > 1, "the prelude scope is popped");
+        self.scopes.pop();
+    }
+
+    /// Declares the fixity of an operator in the innermost scope.
+    pub fn declare(&mut self, op: Symbol, fixity: Fixity) {
+        self.scopes.last_mut().unwrap().insert(op, fixity);
+    }
+}
+
+struct FixityResolver<'diag> {
+    env: FixityEnv,
+    diag: &'diag DiagnosticsEngine,
+}
+
+impl<'diag> FixityResolver<'diag> {
+    fn new(diag: &'diag DiagnosticsEngine) -> FixityResolver<'diag> {
+        FixityResolver { env: FixityEnv::with_prelude(), diag }
+    }
+
+    fn resolve_expr(&mut self, expr: &mut Expr) {
+        match &mut expr.kind {
+            ExprKind::Error
+            | ExprKind::Unit
+            | ExprKind::Lit(_)
+            | ExprKind::Ident(_) => (),
+            ExprKind::PendingSeq(..) => {
+                let kind = mem::replace(&mut expr.kind, ExprKind::Unit);
+                let ExprKind::PendingSeq(first, rest) = kind else {
+                    unreachable!()
+                };
+                *expr = self.resolve_seq(*first, rest);
+            }
+            ExprKind::InfixOp(_, lhs, rhs) => {
+                self.resolve_expr(lhs);
+                self.resolve_expr(rhs);
+            }
+            ExprKind::Infix(fixity, ops, body) => {
+                self.env.push_scope();
+                for op in ops.iter() {
+                    self.env.declare(op.name, *fixity);
+                }
+                self.resolve_expr(body);
+                self.env.pop_scope();
+            }
+            ExprKind::Fn(_, body) => self.resolve_expr(body),
+            ExprKind::FnCall(func, args) => {
+                self.resolve_expr(func);
+                args.iter_mut().for_each(|arg| self.resolve_expr(arg));
+            }
+            ExprKind::If(cond, then, else_) => {
+                self.resolve_expr(cond);
+                self.resolve_expr(then);
+                self.resolve_expr(else_);
+            }
+            ExprKind::Paren(expr) => self.resolve_expr(expr),
+            ExprKind::Block(items) => {
+                items.iter_mut().for_each(|item| self.resolve_expr(item));
+            }
+            ExprKind::Let(_, value, body) => {
+                self.resolve_expr(value);
+                self.resolve_expr(body);
+            }
+        }
+    }
+
+    /// Builds the operator tree of a pending sequence with the operator
+    /// precedence (shunting-yard) algorithm.
+    fn resolve_seq(&mut self, first: Expr, rest: Vec<(Operator, Expr)>) -> Expr {
+        let mut operands = Vec::with_capacity(rest.len() + 1);
+        let mut operators: Vec<(Operator, Fixity)> = Vec::with_capacity(rest.len());
+
+        let mut first = first;
+        self.resolve_expr(&mut first);
+        operands.push(first);
+
+        for (op, mut operand) in rest {
+            self.resolve_expr(&mut operand);
+            let fixity = self.env.lookup(op.name);
+
+            while let Some(&(top, top_fixity)) = operators.last() {
+                if !self.should_reduce(top, top_fixity, op, fixity) {
+                    break;
+                }
+                operators.pop();
+                reduce(&mut operands, top);
+            }
+
+            operators.push((op, fixity));
+            operands.push(operand);
+        }
+
+        while let Some((op, _)) = operators.pop() {
+            reduce(&mut operands, op);
+        }
+
+        debug_assert!(operands.len() == 1);
+        operands.pop().unwrap()
+    }
+
+    /// Returns true if the operator `top` on the stack should be reduced
+    /// before the incoming operator `next` is pushed.
+    fn should_reduce(
+        &self,
+        top: Operator, top_fixity: Fixity,
+        next: Operator, next_fixity: Fixity,
+    ) -> bool {
+        if top_fixity.prec != next_fixity.prec {
+            return top_fixity.prec > next_fixity.prec;
+        }
+
+        match (top_fixity.assoc, next_fixity.assoc) {
+            (Assoc::Left, Assoc::Left) => true,
+            (Assoc::Right, Assoc::Right) => false,
+            _ => {
+                let message = format!(
+                    "cannot mix `{}` and `{}` in the same infix expression",
+                    top.name, next.name,
+                );
+                self.diag.emit(
+                    Diagnostic::error(message)
+                        .with_primary(next.span, format!(
+                            "`{}` is `{}`", next.name, describe(next_fixity),
+                        ))
+                        .with_secondary(top.span, format!(
+                            "`{}` is `{}`", top.name, describe(top_fixity),
+                        ))
+                        .with_help("use parentheses to group the operands"),
+                );
+                true
+            }
+        }
+    }
+}
+
+/// Pops two operands and pushes them back as one infix expression.
+fn reduce(operands: &mut Vec<Expr>, op: Operator) {
+    let rhs = operands.pop().unwrap();
+    let lhs = operands.pop().unwrap();
+    let span = Span::new(lhs.span.start, rhs.span.end);
+    let kind = ExprKind::InfixOp(op, Box::new(lhs), Box::new(rhs));
+    operands.push(Expr::new(kind, span));
+}
+
+/// Describes a fixity the same way it is declared, e.g. `infixl 6`.
+fn describe(fixity: Fixity) -> String {
+    let keyword = match fixity.assoc {
+        Assoc::Left => "infixl",
+        Assoc::Right => "infixr",
+        Assoc::None => "infix",
+    };
+    format!("{} {}", keyword, fixity.prec)
+}
@@ -0,0 +1,179 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Identifier normalization and the mixed-script lint, following UAX #31 and
+//! UAX #39.
+//!
+//! The same name can be written with different code points, e.g. `é` is
+//! either U+00E9 or `e` followed by U+0301, so identifiers are normalized to
+//! NFC before they are interned. Names mixing scripts, e.g. a Cyrillic `а` in
+//! an otherwise Latin name, look like other names and are reported.
+
+use std::borrow::Cow;
+
+use kona_diagnostic::Diagnostic;
+use kona_source::span::Span;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_script::{Script, ScriptExtension, UnicodeScript};
+
+/// Returns the NFC form of an identifier, the text itself if it is already
+/// normalized, which is always the case for ASCII identifiers.
+pub fn normalize_ident(text: &str) -> Cow<'_, str> {
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(text),
+        _ => Cow::Owned(text.nfc().collect()),
+    }
+}
+
+/// Checks that an identifier is written in a single script, returns a
+/// warning if it is not, e.g. `pаypal` with a Cyrillic `а`.
+///
+/// The common characters, e.g. digits and `_`, go with any script. As UAX #39
+/// "highly restrictive" identifiers, Han may be mixed with Hiragana and
+/// Katakana, with Bopomofo, or with Hangul, and such CJK names may be also
+/// mixed with Latin.
+pub fn mixed_script_lint(name: &str, span: Span) -> Option<Diagnostic> {
+    if name.is_ascii() || is_single_script(name.chars()) {
+        return None;
+    }
+    let mut non_latin = name.chars().filter(|c| c.script() != Script::Latin).peekable();
+    if non_latin.peek().is_some() && resolve(non_latin).is_cjk() {
+        return None;
+    }
+
+    // The first character of each script, in the source order.
+    let mut scripts: Vec<(Script, char)> = Vec::new();
+    for c in name.chars() {
+        let script = c.script();
+        if !matches!(script, Script::Common | Script::Inherited)
+            && scripts.iter().all(|(seen, _)| *seen != script)
+        {
+            scripts.push((script, c));
+        }
+    }
+    let names: Vec<_> = scripts.iter().map(|(script, _)| script.full_name()).collect();
+    let names = match names.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, init)) => format!("{} and {}", init.join(", "), last),
+        None => String::new(),
+    };
+    let mut diagnostic = Diagnostic::warning(format!(
+        "identifier `{}` mixes {} characters",
+        name, names,
+    ))
+    .with_primary(span, "");
+    for (script, c) in scripts.iter().skip(1) {
+        diagnostic = diagnostic.with_note(format!(
+            "`{}` (U+{:04X}) is a {} character",
+            c, *c as u32, script.full_name(),
+        ));
+    }
+    Some(diagnostic.with_help(
+        "characters of different scripts may look the same, write the name in one script",
+    ))
+}
+
+fn is_single_script(chars: impl Iterator<Item = char>) -> bool {
+    !resolve(chars).is_empty()
+}
+
+/// Returns the resolved script set of the characters, the scripts shared by
+/// all of them.
+fn resolve(chars: impl Iterator<Item = char>) -> ScriptSet {
+    chars.fold(ScriptSet::all(), |set, c| set.intersection(ScriptSet::of(c)))
+}
+
+/// An augmented script set of UAX #39, the scripts of the `Script_Extensions`
+/// property and the writing systems mixing Han with other scripts.
+#[derive(Clone, Copy)]
+struct ScriptSet {
+    scripts: ScriptExtension,
+
+    /// Han with Bopomofo.
+    hanb: bool,
+
+    /// Han with Hiragana and Katakana.
+    jpan: bool,
+
+    /// Han with Hangul.
+    kore: bool,
+}
+
+impl ScriptSet {
+    /// The set of all scripts, the script set of a common character.
+    fn all() -> ScriptSet {
+        ScriptSet { scripts: ScriptExtension::default(), hanb: true, jpan: true, kore: true }
+    }
+
+    fn of(c: char) -> ScriptSet {
+        // `contains_script` is true for the common characters.
+        let scripts = c.script_extension();
+        let han = scripts.contains_script(Script::Han);
+        ScriptSet {
+            scripts,
+            hanb: han || scripts.contains_script(Script::Bopomofo),
+            jpan: han
+                || scripts.contains_script(Script::Hiragana)
+                || scripts.contains_script(Script::Katakana),
+            kore: han || scripts.contains_script(Script::Hangul),
+        }
+    }
+
+    fn intersection(self, other: ScriptSet) -> ScriptSet {
+        ScriptSet {
+            scripts: self.scripts.intersection(other.scripts),
+            hanb: self.hanb && other.hanb,
+            jpan: self.jpan && other.jpan,
+            kore: self.kore && other.kore,
+        }
+    }
+
+    fn is_cjk(self) -> bool {
+        self.hanb || self.jpan || self.kore
+    }
+
+    fn is_empty(self) -> bool {
+        self.scripts.is_empty() && !self.is_cjk()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kona_source::pos::Pos;
+
+    use super::*;
+
+    fn lint(name: &str) -> Option<String> {
+        let span = Span::new(Pos::from_usize(0), Pos::from_usize(name.len()));
+        mixed_script_lint(name, span).map(|diagnostic| diagnostic.message)
+    }
+
+    #[test]
+    fn test_normalize_ident() {
+        assert!(matches!(normalize_ident("café"), Cow::Borrowed("café")));
+        assert_eq!(normalize_ident("cafe\u{301}"), "caf\u{E9}");
+        assert_eq!(normalize_ident("\u{212B}"), "\u{C5}");
+    }
+
+    #[test]
+    fn test_mixed_script_lint() {
+        assert_eq!(lint("x_1"), None);
+        assert_eq!(lint("größe_2"), None);
+        assert_eq!(lint("значение"), None);
+        assert_eq!(lint("変数の値"), None);
+        assert_eq!(lint("ユーザー名_id"), None);
+        assert_eq!(lint("사용자_名前"), None);
+        assert_eq!(
+            lint("p\u{430}ypal"),
+            Some("identifier `p\u{430}ypal` mixes Latin and Cyrillic characters".to_string()),
+        );
+        assert_eq!(
+            lint("\u{3b1}b\u{430}"),
+            Some("identifier `\u{3b1}b\u{430}` mixes Greek, Latin and Cyrillic characters".to_string()),
+        );
+        assert_eq!(
+            lint("の사"),
+            Some("identifier `の사` mixes Hiragana and Hangul characters".to_string()),
+        );
+    }
+}
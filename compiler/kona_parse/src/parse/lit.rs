@@ -0,0 +1,419 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Literal decoding, turns the text of literal tokens into their values.
+//!
+//! The lexer only finds the extent of a literal, e.g. it skips the escape
+//! sequences in a string. The values are decoded here, and the errors in them
+//! are reported, e.g. an integer out of range, an invalid digit in a number, or
+//! an unknown escape sequence.
+
+use std::ops::Range;
+
+use kona_ast::expr::lit::LitValue;
+use kona_diagnostic::{Diagnostic, DiagnosticsEngine};
+use kona_memory::intern::symbol::Symbol;
+use kona_source::span::Span;
+
+use crate::lex::token::LitKind;
+
+/// Decodes a literal, `text` is the literal as it is written in the source
+/// code, including the quotes of a string or character and the leading `-` of
+/// a negative number, and `span` is the span of `text`.
+///
+/// The errors are reported to `diag`. An invalid number or character is
+/// decoded as [`LitValue::Err`], while an invalid escape sequence is kept as
+/// it is in the string.
+pub fn decode_lit(kind: &LitKind, text: &str, span: Span, diag: &DiagnosticsEngine) -> LitValue {
+    match kind {
+        LitKind::Int | LitKind::Float => decode_number(text, span, diag),
+        LitKind::Bool => LitValue::Bool(text == "true"),
+        LitKind::String { terminated } => {
+            let (content, content_span) = quoted_content(text, span, *terminated);
+            let (value, _) = unescape(content, content_span, diag);
+            LitValue::String(Symbol::intern(&value))
+        }
+
+        // The unterminated literal is reported by the lexer.
+        LitKind::Char { terminated: false } => LitValue::Err,
+        LitKind::Char { terminated: true } => {
+            let (content, content_span) = quoted_content(text, span, true);
+            let (value, valid) = unescape(content, content_span, diag);
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if valid => LitValue::Char(c),
+                (None, _) => {
+                    diag.emit(
+                        Diagnostic::error("empty character literal")
+                            .with_primary(span, "expected a character"),
+                    );
+                    LitValue::Err
+                }
+                (Some(_), Some(_)) if valid => {
+                    diag.emit(
+                        Diagnostic::error("character literal may only contain one character")
+                            .with_primary(span, "")
+                            .with_help(
+                                "if you meant to write a string literal, use double quotes",
+                            ),
+                    );
+                    LitValue::Err
+                }
+                _ => LitValue::Err,
+            }
+        }
+    }
+}
+
+/// Returns the content of a string or character literal without the quotes,
+/// and the span of the content.
+fn quoted_content(text: &str, span: Span, terminated: bool) -> (&str, Span) {
+    let content = &text[1..];
+    let content = if terminated { &content[..content.len() - 1] } else { content };
+    (content, Span::new(span.start + 1, span.start + 1 + content.len()))
+}
+
+/// Decodes a number literal, e.g. `-0xFF`, `1_000` or `6.02e23`. The literal
+/// is a float if it has a fraction or an exponent.
+fn decode_number(text: &str, span: Span, diag: &DiagnosticsEngine) -> LitValue {
+    let mut decoder = NumberDecoder { text, span, diag, has_errors: false };
+    let mut pos = usize::from(text.starts_with('-'));
+    let radix = match text.get(pos..pos + 2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+
+    let mut is_float = false;
+    if radix != 10 {
+        let end = decoder.digits(pos + 2, radix);
+        if end == pos + 2 {
+            decoder.error(pos..end, "missing digits after the integer base prefix", "");
+        }
+        pos = end;
+    } else {
+        pos = decoder.digits(pos, 10);
+        if text[pos..].starts_with('.') {
+            pos = decoder.digits(pos + 1, 10);
+            is_float = true;
+        }
+        if text[pos..].starts_with(['e', 'E']) {
+            let mut start = pos + 1;
+            if text[start..].starts_with(['+', '-']) {
+                start += 1;
+            }
+            let end = decoder.digits(start, 10);
+            if end == start {
+                decoder.error(pos..end, "expected at least one digit in exponent", "");
+            }
+            pos = end;
+            is_float = true;
+        }
+    }
+    if pos < text.len() {
+        decoder.error(
+            pos..text.len(),
+            format!("invalid suffix `{}` for number literal", &text[pos..]),
+            "invalid suffix",
+        );
+    }
+    if decoder.has_errors {
+        return LitValue::Err;
+    }
+
+    let digits: String = text.chars().filter(|c| *c != '_').collect();
+    if is_float {
+        return match digits.parse::<f64>() {
+            Ok(value) if value.is_finite() => LitValue::Float(value),
+            _ => {
+                diag.emit(
+                    Diagnostic::error("float literal is out of range for type `float`")
+                        .with_primary(span, ""),
+                );
+                LitValue::Err
+            }
+        };
+    }
+
+    // Parse the magnitude, then apply the sign, so `-0x8000000000000000` is
+    // in range.
+    let negative = digits.starts_with('-');
+    let magnitude = &digits[usize::from(negative)..];
+    let magnitude = if radix == 10 { magnitude } else { &magnitude[2..] };
+    let value = u64::from_str_radix(magnitude, radix).ok().and_then(|magnitude| {
+        if negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    });
+    match value {
+        Some(value) => LitValue::Int(value),
+        None => {
+            diag.emit(
+                Diagnostic::error("integer literal is out of range for type `int`")
+                    .with_primary(span, "")
+                    .with_note(format!(
+                        "the range of type `int` is `{}..={}`",
+                        i64::MIN, i64::MAX,
+                    )),
+            );
+            LitValue::Err
+        }
+    }
+}
+
+/// Checks the parts of a number literal, and reports the errors in them with
+/// the spans of the offending characters.
+struct NumberDecoder<'a> {
+    text: &'a str,
+    span: Span,
+    diag: &'a DiagnosticsEngine,
+    has_errors: bool,
+}
+
+impl NumberDecoder<'_> {
+    /// Checks the digits of the given radix and `_`s starting at `start`, and
+    /// returns the end of them. A `_` must be between digits, e.g. `1_000`.
+    ///
+    /// The letters are taken as digits for a non-decimal radix, so that an
+    /// invalid one is reported as a digit, e.g. `0b12` or `0xFG`.
+    fn digits(&mut self, start: usize, radix: u32) -> usize {
+        let rest = &self.text[start..];
+        let is_digit_part = |c: char| {
+            c == '_' || c.is_ascii_digit() || (radix != 10 && c.is_alphanumeric())
+        };
+        let len = rest.find(|c| !is_digit_part(c)).unwrap_or(rest.len());
+        let end = start + len;
+
+        let digits = &rest[..len];
+        let invalid = digits.char_indices().find(|&(_, c)| c != '_' && !c.is_digit(radix));
+        if let Some((index, c)) = invalid {
+            let index = start + index;
+            self.error(
+                index..index + c.len_utf8(),
+                format!("invalid digit `{}` in {} literal", c, radix_name(radix)),
+                "invalid digit",
+            );
+        } else if digits.starts_with('_') {
+            self.error(start..start + 1, "`_` must come after a digit", "");
+        } else if digits.ends_with('_') {
+            self.error(end - 1..end, "trailing `_` in number literal", "");
+        }
+        end
+    }
+
+    fn error(&mut self, range: Range<usize>, message: impl Into<String>, label: &str) {
+        let start = self.span.start + range.start;
+        let span = Span::new(start, start + range.len());
+        self.diag.emit(Diagnostic::error(message).with_primary(span, label));
+        self.has_errors = true;
+    }
+}
+
+fn radix_name(radix: u32) -> &'static str {
+    match radix {
+        2 => "binary",
+        8 => "octal",
+        16 => "hexadecimal",
+        _ => "decimal",
+    }
+}
+
+/// Replaces the escape sequences in the content of a string or character
+/// literal, returns the result and false if there are invalid escape
+/// sequences. The invalid ones are reported and kept as they are.
+fn unescape(text: &str, span: Span, diag: &DiagnosticsEngine) -> (String, bool) {
+    let mut result = String::with_capacity(text.len());
+    let mut valid = true;
+    let mut pos = 0;
+    while let Some(offset) = text[pos..].find('\\') {
+        let start = pos + offset;
+        result.push_str(&text[pos..start]);
+        pos = match unescape_char(&text[start..]) {
+            Ok((c, len)) => {
+                result.push(c);
+                start + len
+            }
+            Err((len, diagnostic)) => {
+                let escape_span = Span::new(span.start + start, span.start + start + len);
+                diag.emit(diagnostic.with_primary(escape_span, ""));
+                result.push_str(&text[start..start + len]);
+                valid = false;
+                start + len
+            }
+        };
+    }
+    result.push_str(&text[pos..]);
+    (result, valid)
+}
+
+/// Decodes the escape sequence at the start of `escape`, returns the
+/// character and the length of the escape sequence, or the length of the
+/// invalid escape sequence and the error without a label.
+fn unescape_char(escape: &str) -> Result<(char, usize), (usize, Diagnostic)> {
+    let c = match escape[1..].chars().next() {
+        Some('0') => '\0',
+        Some('t') => '\t',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some(c @ ('\\' | '"' | '\'')) => c,
+        Some('u') => return unescape_unicode(escape),
+        Some(c) => {
+            let diagnostic = Diagnostic::error(format!(
+                "unknown character escape: `{}`",
+                c.escape_default(),
+            ))
+            .with_help(
+                "the valid escapes are `\\0`, `\\\\`, `\\t`, `\\n`, `\\r`, `\\\"`, `\\'` and `\\u{...}`",
+            );
+            return Err((1 + c.len_utf8(), diagnostic));
+        }
+
+        // The backslash at the end of an unterminated literal, the literal
+        // is reported by the lexer.
+        None => return Ok(('\\', 1)),
+    };
+    Ok((c, 2))
+}
+
+/// Decodes a unicode escape sequence, e.g. `\u{1F600}`, with 1 to 6
+/// hexadecimal digits.
+fn unescape_unicode(escape: &str) -> Result<(char, usize), (usize, Diagnostic)> {
+    let format_error = |len| {
+        let diagnostic = Diagnostic::error("incorrect unicode escape sequence")
+            .with_help("the format of unicode escapes is `\\u{...}`, with 1 to 6 hex digits");
+        Err((len, diagnostic))
+    };
+    let Some(rest) = escape.strip_prefix("\\u{") else {
+        return format_error(2);
+    };
+    let digits = rest.find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(rest.len());
+    if !rest[digits..].starts_with('}') {
+        return format_error(3 + digits);
+    }
+
+    let len = 4 + digits;
+    if digits == 0 {
+        return Err((len, Diagnostic::error("empty unicode escape")));
+    }
+    let value = u32::from_str_radix(&rest[..digits], 16).ok().filter(|_| digits <= 6);
+    match value.and_then(char::from_u32) {
+        Some(c) => Ok((c, len)),
+        None => {
+            let diagnostic = Diagnostic::error("invalid unicode character escape").with_help(
+                "unicode escapes must be at most `10FFFF`, and not a surrogate from `D800` to `DFFF`",
+            );
+            Err((len, diagnostic))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use kona_source::{pos::Pos, source_map::SourceMap};
+
+    use super::*;
+
+    /// Decodes the literal, returns its value and the spans of the errors,
+    /// relative to the start of the literal.
+    fn decode(kind: LitKind, text: &str) -> (LitValue, Vec<(usize, usize)>) {
+        let sm = Rc::new(SourceMap::new());
+        let diag = DiagnosticsEngine::new(sm);
+        let span = Span::new(Pos::from_usize(0), Pos::from_usize(text.len()));
+        let value = decode_lit(&kind, text, span, &diag);
+        let errors = diag.take_diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().unwrap();
+                (span.start.to_usize(), span.end.to_usize())
+            })
+            .collect();
+        (value, errors)
+    }
+
+    fn string(text: &str) -> LitValue {
+        LitValue::String(Symbol::intern(text))
+    }
+
+    #[test]
+    fn test_decode_numbers() {
+        assert_eq!(decode(LitKind::Int, "42"), (LitValue::Int(42), vec![]));
+        assert_eq!(
+            decode(LitKind::Int, "-9223372036854775808"),
+            (LitValue::Int(i64::MIN), vec![]),
+        );
+        assert_eq!(
+            decode(LitKind::Int, "9223372036854775808"),
+            (LitValue::Err, vec![(0, 19)]),
+        );
+        assert_eq!(decode(LitKind::Float, "-1.5"), (LitValue::Float(-1.5), vec![]));
+        let huge = format!("1{}.0", "0".repeat(400));
+        assert_eq!(decode(LitKind::Float, &huge), (LitValue::Err, vec![(0, huge.len())]));
+        assert_eq!(decode(LitKind::Bool, "false"), (LitValue::Bool(false), vec![]));
+    }
+
+    #[test]
+    fn test_decode_number_forms() {
+        assert_eq!(decode(LitKind::Int, "0xFF"), (LitValue::Int(255), vec![]));
+        assert_eq!(decode(LitKind::Int, "0b1010"), (LitValue::Int(10), vec![]));
+        assert_eq!(decode(LitKind::Int, "-0o755"), (LitValue::Int(-493), vec![]));
+        assert_eq!(decode(LitKind::Int, "1_000_000"), (LitValue::Int(1_000_000), vec![]));
+        assert_eq!(
+            decode(LitKind::Int, "-0x8000_0000_0000_0000"),
+            (LitValue::Int(i64::MIN), vec![]),
+        );
+        assert_eq!(
+            decode(LitKind::Int, "0x8000_0000_0000_0000"),
+            (LitValue::Err, vec![(0, 21)]),
+        );
+        assert_eq!(decode(LitKind::Float, "6.02e23"), (LitValue::Float(6.02e23), vec![]));
+        assert_eq!(decode(LitKind::Float, "1_0E-2"), (LitValue::Float(0.1), vec![]));
+        assert_eq!(decode(LitKind::Float, "1e400"), (LitValue::Err, vec![(0, 5)]));
+
+        assert_eq!(decode(LitKind::Int, "0x"), (LitValue::Err, vec![(0, 2)]));
+        assert_eq!(decode(LitKind::Int, "0b1021"), (LitValue::Err, vec![(4, 5)]));
+        assert_eq!(decode(LitKind::Int, "0x_1"), (LitValue::Err, vec![(2, 3)]));
+        assert_eq!(decode(LitKind::Int, "1_000_"), (LitValue::Err, vec![(5, 6)]));
+        assert_eq!(decode(LitKind::Float, "1_.5_e3"), (LitValue::Err, vec![(1, 2), (4, 5)]));
+        assert_eq!(decode(LitKind::Float, "2e+"), (LitValue::Err, vec![(1, 3)]));
+        assert_eq!(decode(LitKind::Int, "12px"), (LitValue::Err, vec![(2, 4)]));
+    }
+
+    #[test]
+    fn test_decode_strings() {
+        let terminated = LitKind::String { terminated: true };
+        assert_eq!(
+            decode(terminated.clone(), r#""a\tb\\\"\0""#),
+            (string("a\tb\\\"\0"), vec![]),
+        );
+        assert_eq!(
+            decode(terminated, r#""é\q\n\✓""#),
+            (string("é\\q\n\\✓"), vec![(3, 5), (7, 11)]),
+        );
+        assert_eq!(
+            decode(LitKind::String { terminated: false }, r#""abc\"#),
+            (string("abc\\"), vec![]),
+        );
+        assert_eq!(
+            decode(LitKind::String { terminated: true }, r#""\u{48}\u{1F600}\u{}\u{110000}\u41""#),
+            (string("H\u{1F600}\\u{}\\u{110000}\\u41"), vec![(16, 20), (20, 30), (30, 32)]),
+        );
+    }
+
+    #[test]
+    fn test_decode_chars() {
+        let char = LitKind::Char { terminated: true };
+        assert_eq!(decode(char.clone(), "'a'"), (LitValue::Char('a'), vec![]));
+        assert_eq!(decode(char.clone(), "'\\''"), (LitValue::Char('\''), vec![]));
+        assert_eq!(decode(char.clone(), "'\"'"), (LitValue::Char('"'), vec![]));
+        assert_eq!(decode(char.clone(), "'\\u{1F600}'"), (LitValue::Char('\u{1F600}'), vec![]));
+        assert_eq!(decode(char.clone(), "''"), (LitValue::Err, vec![(0, 2)]));
+        assert_eq!(decode(char.clone(), "'ab'"), (LitValue::Err, vec![(0, 4)]));
+        assert_eq!(decode(char, "'\\q'"), (LitValue::Err, vec![(1, 3)]));
+        assert_eq!(decode(LitKind::Char { terminated: false }, "'a"), (LitValue::Err, vec![]));
+    }
+}
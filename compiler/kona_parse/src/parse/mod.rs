@@ -1,29 +1,1319 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use std::rc::Rc;
+use std::collections::HashSet;
 
-use kona_ast::expr::Expr;
-use kona_diagnostic::DiagnosticsEngine;
-use kona_source::{source_map::{self, SourceMap}, source_file::SourceFile};
+use kona_ast::{
+    decl::{Decl, Item},
+    expr::{
+        Expr, ExprKind,
+        ident::Ident,
+        lit::{Lit, LitKind},
+        operator::{Assoc, Fixity, Operator},
+    },
+};
+use kona_diagnostic::{Diagnostic, DiagnosticsEngine};
+use kona_memory::intern::symbol::{kw, Symbol};
+use kona_source::{pos::Pos, source_file::SourceFile, span::Span};
+use kona_syntax::{
+    green::{Checkpoint, GreenNodeBuilder},
+    kind::SyntaxKind,
+    node::SyntaxNode,
+};
 
-use crate::lex::token_iter::TokenIter;
+use crate::lex::{token::{self, Token, TokenKind}, token_iter::TokenIter};
 
+pub mod fixity;
+pub mod ident;
+pub mod lit;
+
+/// Parses the whole source file as a top-level expression, and resolves the
+/// infix expressions in it.
+///
+/// All syntax errors are reported to `diag`. The parser recovers from errors,
+/// an expression is always returned, the erroneous parts of it are replaced by
+/// [`ExprKind::Error`]s. Check [`DiagnosticsEngine::has_errors`] before going
+/// on to the next pass.
+pub fn parse_file(source_file: &SourceFile, diag: &DiagnosticsEngine) -> Expr {
+    let mut expr = Parser::new(source_file, diag).parse_file();
+    fixity::resolve_fixity(&mut expr, diag);
+    expr
+}
+
+/// Parses the whole source file like [`parse_file`], and also returns the
+/// lossless concrete syntax tree of it, with the whitespace and comments.
+pub fn parse_file_with_cst(
+    source_file: &SourceFile,
+    diag: &DiagnosticsEngine,
+) -> (Expr, SyntaxNode) {
+    let mut parser = Parser::new(source_file, diag);
+    let mut expr = parser.parse_file();
+    let tree = parser.finish_syntax_tree();
+    fixity::resolve_fixity(&mut expr, diag);
+    (expr, tree)
+}
+
+/// Parses the whole source file as a sequence of declarations, e.g. the
+/// prelude, and resolves the infix expressions in them. Errors are reported
+/// like [`parse_file`].
+pub fn parse_decls(source_file: &SourceFile, diag: &DiagnosticsEngine) -> Vec<Decl> {
+    let mut decls = Parser::new(source_file, diag).parse_decls();
+    for decl in &mut decls {
+        fixity::resolve_fixity(&mut decl.value, diag);
+    }
+    decls
+}
+
+/// Parses the whole source file as a declaration or an expression, and
+/// resolves the infix expressions in it. Errors are reported like
+/// [`parse_file`].
+pub fn parse_item(source_file: &SourceFile, diag: &DiagnosticsEngine) -> Item {
+    let mut item = Parser::new(source_file, diag).parse_item();
+    match &mut item {
+        Item::Decl(decl) => fixity::resolve_fixity(&mut decl.value, diag),
+        Item::Expr(expr) => fixity::resolve_fixity(expr, diag),
+    }
+    item
+}
+
+/// Parses the whole source file as a program or as a sequence of declarations,
+/// see [`Parser::parse_items`], and resolves the infix expressions in it. Also
+/// returns the concrete syntax tree like [`parse_file_with_cst`].
+pub fn parse_items_with_cst(
+    source_file: &SourceFile,
+    diag: &DiagnosticsEngine,
+) -> (Vec<Item>, SyntaxNode) {
+    let mut parser = Parser::new(source_file, diag);
+    let mut items = parser.parse_items();
+    let tree = parser.finish_syntax_tree();
+    for item in &mut items {
+        match item {
+            Item::Decl(decl) => fixity::resolve_fixity(&mut decl.value, diag),
+            Item::Expr(expr) => fixity::resolve_fixity(expr, diag),
+        }
+    }
+    (items, tree)
+}
+
+/// The maximum nesting depth of expressions. The parser and the passes after
+/// it walk the expressions recursively, a deeper expression would overflow the
+/// stack.
+///
+/// The depth counts the nested expressions and the operands of an infix or
+/// application expression, which become nested expressions once resolved, e.g.
+/// `a + b + c` is two levels deep.
+pub const MAX_NESTING_DEPTH: usize = 5000;
+
+/// A recursive descent parser for the Kona grammar described in KP-00001.
+///
+/// The parser never stops at a syntax error. It reports the error, inserts an
+/// [`ExprKind::Error`] placeholder, skips to a synchronization point (`in`,
+/// `then`, `else`, `;`, `)` or the end of file), and keeps going.
+///
+/// Along with the AST, the parser builds the lossless concrete syntax tree of
+/// the file, see [`Parser::finish_syntax_tree`]. The trivia between tokens is
+/// put in the innermost node containing both tokens, so a node never starts
+/// or ends with trivia.
 pub struct Parser<'src> {
     tokens: TokenIter<'src>,
-    diag: DiagnosticsEngine,
+    source_file: &'src SourceFile,
+    diag: &'src DiagnosticsEngine,
+
+    /// The span of the last eaten token, used to compute the end position of
+    /// a syntax node.
+    prev_span: Span,
+
+    /// The position of the last reported error.
+    ///
+    /// One mistake usually causes a series of errors at the same position,
+    /// e.g. `let x = 1` reports both "expected `in`" and "expected expression"
+    /// at the end of file. Only the first one is reported.
+    last_err_pos: Option<Pos>,
+
+    /// The number of enclosing group expressions. A `)` is a synchronization
+    /// point only when it may close one of them.
+    group_depth: usize,
+
+    /// The nesting depth of the expression being parsed, see
+    /// [`MAX_NESTING_DEPTH`].
+    depth: usize,
+
+    /// Whether an expression nested too deeply was found. The rest of the file
+    /// is skipped then, and no more errors are reported.
+    too_deep: bool,
+
+    /// The concrete syntax tree built so far.
+    builder: GreenNodeBuilder,
+
+    /// The identifiers reported by [`ident::mixed_script_lint`], a name is
+    /// only reported at its first occurrence.
+    mixed_script_idents: HashSet<Symbol>,
 }
 
 impl<'src> Parser<'src> {
-    pub fn new(source_file: &'src Rc<SourceFile>, source_map: Rc<SourceMap>) -> Parser<'src> {
-        // FIXME: I really hate `&'a Rc<T>`, but I haven't figured out how to
-        // fix it.
-        let tokens = TokenIter::new(&source_file.src, source_file.start_pos);
-        let diag = DiagnosticsEngine::new(source_map);
-        Parser { tokens, diag }
+    pub fn new(source_file: &'src SourceFile, diag: &'src DiagnosticsEngine) -> Parser<'src> {
+        let tokens = TokenIter::with_diagnostics(&source_file.src, source_file.start_pos, diag);
+        let prev_span = Span::new(source_file.start_pos, source_file.start_pos);
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(SyntaxKind::Root);
+        Parser {
+            tokens, source_file, diag, prev_span, builder,
+            last_err_pos: None,
+            group_depth: 0,
+            depth: 0,
+            too_deep: false,
+            mixed_script_idents: HashSet::new(),
+        }
+    }
+
+    /// Returns the concrete syntax tree of the file. The tokens not parsed yet
+    /// are put in an error node, so the tree always covers the whole file.
+    pub fn finish_syntax_tree(mut self) -> SyntaxNode {
+        self.skip_until(|_| false);
+        self.flush_trivia();
+        self.builder.finish_node();
+        SyntaxNode::new_root(self.builder.finish(), self.source_file.start_pos)
+    }
+
+    /// Parses a top-level expression, the whole file must be consumed.
+    pub fn parse_file(&mut self) -> Expr {
+        let expr = self.parse_expr();
+        self.parse_trailing_tokens();
+        expr
+    }
+
+    /// Parses a declaration or an expression, the whole file must be
+    /// consumed. A `let` is a declaration if it is not followed by `in`.
+    ///
+    /// ```text
+    /// item ::= declaration | expression
+    /// ```
+    pub fn parse_item(&mut self) -> Item {
+        let item = self.parse_leading_item();
+        self.parse_trailing_tokens();
+        item
+    }
+
+    /// Parses a whole file which is either a program or a sequence of
+    /// declarations, e.g. the prelude. It is a sequence of declarations if it
+    /// starts with one, a `let` not followed by `in`.
+    pub fn parse_items(&mut self) -> Vec<Item> {
+        match self.parse_leading_item() {
+            Item::Decl(decl) => {
+                let decls = self.parse_decls().into_iter().map(Item::Decl);
+                std::iter::once(Item::Decl(decl)).chain(decls).collect()
+            }
+            item @ Item::Expr(_) => {
+                self.parse_trailing_tokens();
+                vec![item]
+            }
+        }
+    }
+
+    /// Parses the first item of a file, see [`Parser::parse_item`].
+    fn parse_leading_item(&mut self) -> Item {
+        if self.peek().kind == TokenKind::Let {
+            let checkpoint = self.checkpoint();
+            let decl = self.parse_decl();
+            if self.peek().kind == TokenKind::In {
+                self.eat();
+                let body = self.parse_expr();
+                self.wrap_node(checkpoint, SyntaxKind::LetExpr);
+                let span = Span::new(decl.span.start, body.span.end);
+                let kind = ExprKind::Let(decl.name, Box::new(decl.value), Box::new(body));
+                Item::Expr(Expr::new(kind, span))
+            } else {
+                self.wrap_node(checkpoint, SyntaxKind::Decl);
+                Item::Decl(decl)
+            }
+        } else {
+            Item::Expr(self.parse_expr())
+        }
+    }
+
+    /// Reports the unexpected tokens before the end of file, and keeps parsing
+    /// the rest of the file to find more errors.
+    fn parse_trailing_tokens(&mut self) {
+        loop {
+            let token = self.peek();
+            if token.kind == TokenKind::Eof {
+                break;
+            }
+            self.unexpected(&token, "end of file");
+            let checkpoint = self.checkpoint();
+            self.eat();
+            if self.is_expr_start() {
+                self.parse_expr();
+            }
+            self.wrap_node(checkpoint, SyntaxKind::Error);
+        }
+    }
+
+    /// Parses declarations until the end of file.
+    ///
+    /// ```text
+    /// declarations ::= declaration declarations?
+    /// ```
+    pub fn parse_decls(&mut self) -> Vec<Decl> {
+        let mut decls = Vec::new();
+        loop {
+            let token = self.peek();
+            match token.kind {
+                TokenKind::Eof => return decls,
+                TokenKind::Let => {
+                    let checkpoint = self.checkpoint();
+                    decls.push(self.parse_decl());
+                    self.wrap_node(checkpoint, SyntaxKind::Decl);
+                }
+                _ => {
+                    self.unexpected(&token, "`let`");
+                    self.skip_until(|kind| *kind == TokenKind::Let);
+                }
+            }
+        }
+    }
+
+    /// The caller wraps the declaration in a syntax node, it is the head of a
+    /// `let` expression if followed by `in`.
+    ///
+    /// ```text
+    /// declaration ::= 'let' identifier '=' expression
+    /// ```
+    fn parse_decl(&mut self) -> Decl {
+        let lo = self.eat().span;
+        let name = self.parse_ident();
+        self.expect(TokenKind::Eq, "`=`");
+        let value = self.parse_expr();
+
+        let span = Span::new(lo.start, value.span.end);
+        Decl::new(name, value, span)
+    }
+
+    /// Parses an expression.
+    ///
+    /// ```text
+    /// expression ::= infix-expression
+    ///              | lambda-expression
+    ///              | if-expression
+    ///              | let-expression
+    ///              | fixity-expression
+    /// ```
+    pub fn parse_expr(&mut self) -> Expr {
+        if !self.enter_nested() {
+            return self.skip_too_deep();
+        }
+        let expr = match self.peek().kind {
+            TokenKind::Fn => self.parse_fn_expr(),
+            TokenKind::If => self.parse_if_expr(),
+            TokenKind::Let => self.parse_let_expr(),
+            TokenKind::Infix
+            | TokenKind::Infixl
+            | TokenKind::Infixr => self.parse_fixity_expr(),
+            _ => self.parse_infix_expr(),
+        };
+        self.depth -= 1;
+        expr
+    }
+
+    fn is_expr_start(&mut self) -> bool {
+        matches!(
+            self.peek().kind,
+            TokenKind::Fn | TokenKind::If | TokenKind::Let
+                | TokenKind::Infix | TokenKind::Infixl | TokenKind::Infixr
+        ) || self.is_atomic_expr_start()
+    }
+
+    /// ```text
+    /// lambda-expression ::= 'fn' identifier '=>' expression
+    /// ```
+    fn parse_fn_expr(&mut self) -> Expr {
+        self.start_node(SyntaxKind::FnExpr);
+        let lo = self.eat().span;
+        let param = self.parse_ident();
+        self.expect(TokenKind::DArrow, "`=>`");
+        let body = self.parse_expr();
+        self.finish_node();
+
+        let span = Span::new(lo.start, body.span.end);
+        Expr::new(ExprKind::Fn(param, Box::new(body)), span)
+    }
+
+    /// ```text
+    /// if-expression ::= 'if' expression 'then' expression 'else' expression
+    /// ```
+    fn parse_if_expr(&mut self) -> Expr {
+        self.start_node(SyntaxKind::IfExpr);
+        let lo = self.eat().span;
+        let cond = self.parse_expr();
+        self.expect(TokenKind::Then, "`then`");
+        let then = self.parse_expr();
+        self.expect(TokenKind::Else, "`else`");
+        let else_ = self.parse_expr();
+        self.finish_node();
+
+        let span = Span::new(lo.start, else_.span.end);
+        let kind = ExprKind::If(Box::new(cond), Box::new(then), Box::new(else_));
+        Expr::new(kind, span)
+    }
+
+    /// ```text
+    /// let-expression ::= 'let' identifier '=' expression 'in' expression
+    /// ```
+    fn parse_let_expr(&mut self) -> Expr {
+        self.start_node(SyntaxKind::LetExpr);
+        let lo = self.eat().span;
+        let name = self.parse_ident();
+        self.expect(TokenKind::Eq, "`=`");
+        let value = self.parse_expr();
+        self.expect(TokenKind::In, "`in`");
+        let body = self.parse_expr();
+        self.finish_node();
+
+        let span = Span::new(lo.start, body.span.end);
+        let kind = ExprKind::Let(name, Box::new(value), Box::new(body));
+        Expr::new(kind, span)
+    }
+
+    /// ```text
+    /// fixity-expression ::= fixity decimal-digit? operators 'in' expression
+    /// fixity            ::= 'infix' | 'infixl' | 'infixr'
+    /// operators         ::= operator operators?
+    /// ```
+    fn parse_fixity_expr(&mut self) -> Expr {
+        self.start_node(SyntaxKind::FixityExpr);
+        let token = self.eat();
+        let assoc = match token.kind {
+            TokenKind::Infixl => Assoc::Left,
+            TokenKind::Infixr => Assoc::Right,
+            _ => Assoc::None,
+        };
+
+        // The precedence is optional and defaults to 0, as in Standard ML.
+        let mut prec = 0;
+        let next = self.peek();
+        if next.kind == TokenKind::Lit(token::LitKind::Int) {
+            self.eat();
+            match self.token_text(&next).parse::<u8>() {
+                Ok(n) if n <= Fixity::MAX_PREC => prec = n,
+                _ => self.emit(
+                    Diagnostic::error("invalid precedence")
+                        .with_primary(next.span, format!(
+                            "precedence must be a digit from 0 to {}",
+                            Fixity::MAX_PREC,
+                        )),
+                ),
+            }
+        }
+
+        let mut ops = Vec::new();
+        while let Some(op) = self.peek_operator() {
+            self.eat();
+            ops.push(op);
+        }
+        if ops.is_empty() {
+            let token = self.peek();
+            self.unexpected(&token, "operator");
+        }
+
+        self.expect(TokenKind::In, "operator or `in`");
+        let body = self.parse_expr();
+        self.finish_node();
+
+        let span = Span::new(token.span.start, body.span.end);
+        let fixity = Fixity::new(assoc, prec);
+        Expr::new(ExprKind::Infix(fixity, ops, Box::new(body)), span)
+    }
+
+    /// Parses an infix expression as a flat [`ExprKind::PendingSeq`], which
+    /// is resolved into a tree later by the fixity resolution pass.
+    ///
+    /// ```text
+    /// infix-expression ::= application-expression
+    ///                    | application-expression operator infix-expression
+    /// ```
+    fn parse_infix_expr(&mut self) -> Expr {
+        let checkpoint = self.checkpoint();
+        let first = self.parse_app_expr();
+
+        // Each operand is nested one level deeper, see `MAX_NESTING_DEPTH`.
+        let depth = self.depth;
+        let mut rest = Vec::new();
+        while let Some(op) = self.peek_operator() {
+            self.eat();
+            if !self.enter_nested() {
+                rest.push((op, self.skip_too_deep()));
+                break;
+            }
+            rest.push((op, self.parse_app_expr()));
+        }
+        self.depth = depth;
+
+        if rest.is_empty() {
+            first
+        } else {
+            self.wrap_node(checkpoint, SyntaxKind::InfixExpr);
+            let span = Span::new(first.span.start, self.prev_span.end);
+            Expr::new(ExprKind::PendingSeq(Box::new(first), rest), span)
+        }
+    }
+
+    /// ```text
+    /// application-expression ::= atomic-expression
+    ///                          | atomic-expression atomic-expression
+    /// ```
+    fn parse_app_expr(&mut self) -> Expr {
+        let checkpoint = self.checkpoint();
+        let func = self.parse_atomic_expr();
+
+        let depth = self.depth;
+        let mut args = Vec::new();
+        while self.is_atomic_expr_start() {
+            if !self.enter_nested() {
+                args.push(self.skip_too_deep());
+                break;
+            }
+            args.push(self.parse_atomic_expr());
+        }
+        self.depth = depth;
+
+        if args.is_empty() {
+            func
+        } else {
+            self.wrap_node(checkpoint, SyntaxKind::CallExpr);
+            let span = Span::new(func.span.start, self.prev_span.end);
+            Expr::new(ExprKind::FnCall(Box::new(func), args), span)
+        }
+    }
+
+    fn is_atomic_expr_start(&mut self) -> bool {
+        matches!(
+            self.peek().kind,
+            TokenKind::Ident | TokenKind::Lit(_) | TokenKind::LParen
+        )
+    }
+
+    /// ```text
+    /// atomic-expression ::= literal-expression
+    ///                     | identifier
+    ///                     | group-expression
+    /// ```
+    fn parse_atomic_expr(&mut self) -> Expr {
+        let token = self.peek();
+        match token.kind {
+            TokenKind::Ident => {
+                self.start_node(SyntaxKind::NameRef);
+                let ident = self.parse_ident();
+                self.finish_node();
+                Expr::new(ExprKind::Ident(ident), ident.span)
+            }
+            TokenKind::Lit(_) => {
+                self.start_node(SyntaxKind::LitExpr);
+                let lit = self.parse_lit(None);
+                self.finish_node();
+                let span = lit.span;
+                Expr::new(ExprKind::Lit(lit), span)
+            }
+            TokenKind::LParen => self.parse_group_expr(),
+            TokenKind::Op if self.token_text(&token) == "-" => {
+                self.parse_negative_lit()
+            }
+
+            // `f fn x => x` or `1 + if a then b else c`, the user forgot the
+            // parentheses. Parse the whole expression to avoid more errors.
+            TokenKind::Fn | TokenKind::If | TokenKind::Let
+            | TokenKind::Infix | TokenKind::Infixl | TokenKind::Infixr => {
+                let expr = self.parse_expr();
+                self.emit(
+                    Diagnostic::error(format!(
+                        "`{}` expression must be parenthesized here",
+                        self.token_text(&token),
+                    ))
+                    .with_primary(expr.span, "")
+                    .with_help("wrap the expression in parentheses"),
+                );
+                expr
+            }
+
+            _ => {
+                self.unexpected(&token, "expression");
+                self.recover_to_sync_point()
+            }
+        }
+    }
+
+    /// ```text
+    /// group-expression       ::= '(' group-expression-list? ')'
+    /// group-expression-list  ::= group-expression-items expression?
+    ///                          | expression
+    /// group-expression-item  ::= ';'
+    ///                          | expression ';'
+    /// group-expression-items ::= group-expression-item group-expression-items?
+    /// ```
+    fn parse_group_expr(&mut self) -> Expr {
+        let checkpoint = self.checkpoint();
+        let lo = self.eat().span;
+        self.group_depth += 1;
+
+        let mut items = Vec::new();
+        let mut has_semi = false;
+        let mut ends_with_semi = false;
+        loop {
+            match self.peek().kind {
+                TokenKind::RParen | TokenKind::Eof => break,
+                TokenKind::Semi => {
+                    self.eat();
+                    has_semi = true;
+                    ends_with_semi = true;
+                }
+                _ => {
+                    items.push(self.parse_expr());
+                    ends_with_semi = false;
+                    if self.peek().kind != TokenKind::Semi {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let token = self.peek();
+        let hi = if token.kind == TokenKind::RParen {
+            self.eat().span
+        } else {
+            // Skip to the closing parenthesis, the tokens in between can't
+            // be a part of this group.
+            let diagnostic = self.unexpected_diagnostic(&token, "`;` or `)`")
+                .with_secondary(lo, "unclosed delimiter");
+            self.emit(diagnostic);
+            self.skip_until(|kind| kind == &TokenKind::RParen);
+            if self.peek().kind == TokenKind::RParen {
+                self.eat();
+            }
+            self.prev_span
+        };
+        self.group_depth -= 1;
+        let span = Span::new(lo.start, hi.end);
+
+        let (kind, syntax_kind) = if !has_semi {
+            match items.pop() {
+                None => (ExprKind::Unit, SyntaxKind::UnitExpr),
+                Some(expr) => (ExprKind::Paren(Box::new(expr)), SyntaxKind::ParenExpr),
+            }
+        } else {
+            if ends_with_semi {
+                let unit_span = Span::new(hi.start, hi.start);
+                items.push(Expr::new(ExprKind::Unit, unit_span));
+            }
+            (ExprKind::Block(items), SyntaxKind::BlockExpr)
+        };
+        self.wrap_node(checkpoint, syntax_kind);
+        Expr::new(kind, span)
+    }
+
+    /// Parses an identifier and normalizes it to NFC. If the next token is not
+    /// an identifier, an error is reported and an empty identifier is returned.
+    fn parse_ident(&mut self) -> Ident {
+        let token = self.peek();
+        if token.kind == TokenKind::Ident {
+            self.eat();
+            let name = ident::normalize_ident(self.token_text(&token));
+            let ident = Ident::from_str(&name, token.span);
+            if let Some(warning) = ident::mixed_script_lint(&name, token.span) {
+                if self.mixed_script_idents.insert(ident.name) {
+                    self.diag.emit(warning);
+                }
+            }
+            ident
+        } else {
+            self.unexpected(&token, "identifier");
+            let span = Span::new(token.span.start, token.span.start);
+            Ident::new(kw::Empty, span)
+        }
+    }
+
+    /// Parses a literal and decodes its value. `minus` is the span of the `-`
+    /// right before a negative number.
+    fn parse_lit(&mut self, minus: Option<Span>) -> Lit {
+        let token = self.eat();
+        let TokenKind::Lit(token_kind) = &token.kind else {
+            unreachable!("expected a literal token");
+        };
+        let kind = match token_kind {
+            token::LitKind::Int => LitKind::Int,
+            token::LitKind::Float => LitKind::Float,
+            token::LitKind::Bool => LitKind::Bool,
+            token::LitKind::String { .. } => LitKind::String,
+            token::LitKind::Char { .. } => LitKind::Char,
+        };
+        let span = match minus {
+            Some(minus) => Span::new(minus.start, token.span.end),
+            None => token.span,
+        };
+        let text = self.span_text(span);
+        let value = lit::decode_lit(token_kind, text, span, self.diag);
+        Lit::new(kind, Symbol::intern(text), value, span)
+    }
+
+    /// Parses a negative numeric literal, e.g. `-42` or `-1.5`.
+    ///
+    /// ```text
+    /// numberic-literal ::= '-'? integer-literal
+    ///                    | '-'? floating-point-literal
+    /// ```
+    ///
+    /// The lexer produces a `-` operator and a number for this. We only get
+    /// here at the start of an operand, the `-` in `x - 1` is eaten as an infix
+    /// operator before.
+    fn parse_negative_lit(&mut self) -> Expr {
+        let checkpoint = self.checkpoint();
+        let minus = self.eat();
+        let number = self.peek();
+        let is_number = matches!(
+            number.kind,
+            TokenKind::Lit(token::LitKind::Int | token::LitKind::Float)
+        );
+        if !is_number || number.span.start != minus.span.end {
+            self.unexpected(&minus, "expression");
+            self.wrap_node(checkpoint, SyntaxKind::Error);
+            return Expr::new(ExprKind::Error, minus.span);
+        }
+
+        let lit = self.parse_lit(Some(minus.span));
+        self.wrap_node(checkpoint, SyntaxKind::LitExpr);
+        Expr::new(ExprKind::Lit(lit), lit.span)
+    }
+
+    /// Returns the next token as an infix operator if it is one. The `=` token
+    /// is also an operator (the equality) in an infix expression and in a
+    /// fixity declaration.
+    fn peek_operator(&mut self) -> Option<Operator> {
+        let token = self.peek();
+        match token.kind {
+            TokenKind::Op | TokenKind::Eq => {
+                Some(Operator::from_str(self.token_text(&token), token.span))
+            }
+            _ => None,
+        }
+    }
+
+    /// Skips tokens until a synchronization point, and returns an error node
+    /// covering the skipped tokens.
+    fn recover_to_sync_point(&mut self) -> Expr {
+        let lo = self.peek().span;
+        let in_group = self.group_depth > 0;
+        let skipped = self.skip_until(|kind| match kind {
+            TokenKind::In | TokenKind::Then | TokenKind::Else
+                | TokenKind::Semi => true,
+            TokenKind::RParen => in_group,
+            _ => false,
+        });
+
+        let span = if skipped {
+            Span::new(lo.start, self.prev_span.end)
+        } else {
+            // An empty error node stands for the missing expression. It is put
+            // before the trivia, so the enclosing node doesn't end with it.
+            self.builder.start_node(SyntaxKind::Error);
+            self.builder.finish_node();
+            Span::new(lo.start, lo.start)
+        };
+        Expr::new(ExprKind::Error, span)
+    }
+
+    /// Enters a nested expression, returns false if it would be nested deeper
+    /// than [`MAX_NESTING_DEPTH`].
+    fn enter_nested(&mut self) -> bool {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    /// Reports an expression nested too deeply, and skips the rest of the
+    /// file. Returns an error node covering the skipped tokens.
+    fn skip_too_deep(&mut self) -> Expr {
+        let lo = self.peek().span;
+        self.emit(
+            Diagnostic::error("expression is nested too deeply")
+                .with_primary(lo, format!("more than {} levels deep", MAX_NESTING_DEPTH))
+                .with_note("the rest of the file is not parsed"),
+        );
+        self.too_deep = true;
+        let span = if self.skip_until(|_| false) {
+            Span::new(lo.start, self.prev_span.end)
+        } else {
+            self.builder.start_node(SyntaxKind::Error);
+            self.builder.finish_node();
+            Span::new(lo.start, lo.start)
+        };
+        Expr::new(ExprKind::Error, span)
+    }
+
+    /// Skips tokens until the predicate holds for the next token or the end of
+    /// file is reached. Parenthesized tokens are skipped as a whole. Returns
+    /// true if any token is skipped, the skipped tokens are wrapped in an error
+    /// node of the syntax tree.
+    fn skip_until(&mut self, predicate: impl Fn(&TokenKind) -> bool) -> bool {
+        let checkpoint = self.checkpoint();
+        let mut skipped = false;
+        let mut depth = 0usize;
+        loop {
+            let kind = self.peek().kind;
+            if kind == TokenKind::Eof || (depth == 0 && predicate(&kind)) {
+                if skipped {
+                    self.wrap_node(checkpoint, SyntaxKind::Error);
+                }
+                return skipped;
+            }
+            match kind {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            self.eat();
+            skipped = true;
+        }
+    }
+
+    fn peek(&mut self) -> Token {
+        self.tokens.peek().unwrap_or_else(|| self.eof_token())
+    }
+
+    fn eat(&mut self) -> Token {
+        self.flush_trivia();
+        let token = self.tokens.eat().unwrap_or_else(|| self.eof_token());
+        if token.kind != TokenKind::Eof {
+            let text = self.token_text(&token);
+            self.builder.token(token.kind.syntax_kind(), text);
+        }
+        self.prev_span = token.span;
+        token
+    }
+
+    /// Puts the trivia before the next token in the syntax tree.
+    fn flush_trivia(&mut self) {
+        self.tokens.peek();
+        for token in self.tokens.take_trivia() {
+            let text = self.token_text(&token);
+            self.builder.token(token.kind.syntax_kind(), text);
+        }
+    }
+
+    /// Starts a syntax node at the next token, the trivia before the token
+    /// goes to the parent node.
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.flush_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+
+    /// Returns a checkpoint at the next token, for a node whose kind is known
+    /// only after parsing its children, e.g. an infix expression.
+    fn checkpoint(&self) -> Checkpoint {
+        self.builder.checkpoint()
+    }
+
+    /// Wraps everything added to the syntax tree since the checkpoint in a
+    /// node.
+    fn wrap_node(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.builder.start_node_at(checkpoint, kind);
+        self.builder.finish_node();
+    }
+
+    fn eof_token(&self) -> Token {
+        let end_pos = self.source_file.end_pos;
+        Token::new(TokenKind::Eof, Span::new(end_pos, end_pos))
+    }
+
+    /// Eats the next token if it is of the given kind, otherwise reports an
+    /// error and leaves the token there, as if the expected token were
+    /// inserted. `expected` is the description of the expected token.
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> bool {
+        let token = self.peek();
+        if token.kind == kind {
+            self.eat();
+            true
+        } else {
+            self.unexpected(&token, expected);
+            false
+        }
+    }
+
+    /// Reports an "expected ..., found ..." error at the given token.
+    fn unexpected(&mut self, token: &Token, expected: &str) {
+        let diagnostic = self.unexpected_diagnostic(token, expected);
+        self.emit(diagnostic);
+    }
+
+    fn unexpected_diagnostic(&self, token: &Token, expected: &str) -> Diagnostic {
+        let found = match token.kind {
+            TokenKind::Eof => "end of file".to_string(),
+            _ => format!("`{}`", self.token_text(token)),
+        };
+        Diagnostic::error(format!("expected {}, found {}", expected, found))
+            .with_primary(token.span, format!("expected {}", expected))
+    }
+
+    /// Emits a syntax error, unless there is already an error at the same
+    /// position, or the rest of the file is skipped for being too deep.
+    fn emit(&mut self, diagnostic: Diagnostic) {
+        if self.too_deep {
+            return;
+        }
+        let pos = diagnostic.primary_span().map(|span| span.start);
+        if pos.is_some() && self.last_err_pos == pos {
+            return;
+        }
+        self.last_err_pos = pos;
+        self.diag.emit(diagnostic);
+    }
+
+    fn token_text(&self, token: &Token) -> &'src str {
+        self.span_text(token.span)
+    }
+
+    fn span_text(&self, span: Span) -> &'src str {
+        let offset = self.source_file.start_pos.to_usize();
+        let start = span.start.to_usize() - offset;
+        let end = span.end.to_usize() - offset;
+        &self.source_file.src[start..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{rc::Rc, thread};
+
+    use kona_source::{pos::Pos, source_map::SourceMap};
+    use kona_syntax::{
+        ast::{self, AstNode, DocCommentOwner},
+        dump::dump_cst,
+    };
+
+    use super::*;
+
+    /// Dumps the expression as an S-expression for comparison.
+    fn dump(expr: &Expr) -> String {
+        match &expr.kind {
+            ExprKind::Error => "<error>".to_string(),
+            ExprKind::Unit => "()".to_string(),
+            ExprKind::Lit(lit) => lit.symbol.to_string(),
+            ExprKind::Ident(ident) => ident.name.to_string(),
+            ExprKind::PendingSeq(first, rest) => {
+                let rest = rest.iter()
+                    .map(|(op, expr)| format!("{} {}", op.name, dump(expr)))
+                    .collect::<Vec<_>>();
+                format!("(seq {} {})", dump(first), rest.join(" "))
+            }
+            ExprKind::InfixOp(op, lhs, rhs) => {
+                format!("({} {} {})", op.name, dump(lhs), dump(rhs))
+            }
+            ExprKind::Infix(fixity, ops, body) => {
+                let ops = ops.iter()
+                    .map(|op| op.name.to_string())
+                    .collect::<Vec<_>>();
+                format!(
+                    "(infix {:?} {} {} {})",
+                    fixity.assoc, fixity.prec, ops.join(" "), dump(body),
+                )
+            }
+            ExprKind::Fn(param, body) => {
+                format!("(fn {} {})", param.name, dump(body))
+            }
+            ExprKind::FnCall(func, args) => {
+                let args = args.iter().map(dump).collect::<Vec<_>>();
+                format!("(call {} {})", dump(func), args.join(" "))
+            }
+            ExprKind::If(cond, then, else_) => {
+                format!("(if {} {} {})", dump(cond), dump(then), dump(else_))
+            }
+            ExprKind::Paren(expr) => format!("(paren {})", dump(expr)),
+            ExprKind::Block(items) => {
+                let items = items.iter().map(dump).collect::<Vec<_>>();
+                format!("(block {})", items.join(" "))
+            }
+            ExprKind::Let(name, value, body) => {
+                format!("(let {} {} {})", name.name, dump(value), dump(body))
+            }
+        }
+    }
+
+    /// Parses the source, returns the expression and the number of errors.
+    fn parse(src: &str) -> (Expr, usize) {
+        let sm = SourceMap::new();
+        let sf = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let diag = DiagnosticsEngine::new(Rc::new(sm));
+        let expr = parse_file(&sf, &diag);
+        (expr, diag.err_count())
+    }
+
+    fn assert_parse(src: &str, expected: &str) {
+        let (expr, err_count) = parse(src);
+        assert_eq!(err_count, 0, "failed to parse");
+        assert_eq!(dump(&expr), expected);
+    }
+
+    fn assert_parse_err(src: &str, expected: &str, expected_err_count: usize) {
+        let (expr, err_count) = parse(src);
+        assert_eq!(dump(&expr), expected);
+        assert_eq!(err_count, expected_err_count);
     }
 
-    fn parse_expr() -> Expr {
-        todo!()
+    #[test]
+    fn test_parse_atoms() {
+        assert_parse("42", "42");
+        assert_parse("-1.5", "-1.5");
+        assert_parse("0xFF_FF", "0xFF_FF");
+        assert_parse("-6.02e-23", "-6.02e-23");
+        assert_parse("1E5+1", "(+ 1E5 1)");
+        assert_parse("\"hello\"", "\"hello\"");
+        assert_parse("true", "true");
+        assert_parse("name", "name");
+        assert_parse("()", "()");
+        assert_parse("(x)", "(paren x)");
+    }
+
+    #[test]
+    fn test_parse_application_and_infix() {
+        assert_parse("f x y", "(call f x y)");
+        assert_parse("f (g x)", "(call f (paren (call g x)))");
+        assert_parse("1 + 2 * 3", "(+ 1 (* 2 3))");
+        assert_parse("1 - 2 - 3", "(- (- 1 2) 3)");
+        assert_parse("a :: b :: c", "(:: a (:: b c))");
+        assert_parse("f x + g y", "(+ (call f x) (call g y))");
+        assert_parse("x - 1", "(- x 1)");
+        assert_parse("x = -1", "(= x -1)");
+        assert_parse("x +. 1.0 *. y", "(+. x (*. 1.0 y))");
+    }
+
+    #[test]
+    fn test_parse_fixity_declarations() {
+        assert_parse(
+            "infixr 6 - in 1 - 2 - 3",
+            "(infix Right 6 - (- 1 (- 2 3)))",
+        );
+        assert_parse(
+            "infixl 8 ++ <+> in a ++ b * c <+> d",
+            "(infix Left 8 ++ <+> (* (++ a b) (<+> c d)))",
+        );
+        assert_parse("infix == in a", "(infix None 0 == a)");
+        assert_parse(
+            "((infixl 8 + in a * b + c); a * b + c)",
+            "(block (paren (infix Left 8 + (* a (+ b c)))) (+ (* a b) c))",
+        );
+        assert_parse("a ~~> b ~~> c", "(~~> (~~> a b) c)");
+    }
+
+    #[test]
+    fn test_parse_fixity_errors() {
+        assert_parse_err("a = b = c", "(= (= a b) c)", 1);
+        assert_parse_err(
+            "infixl 6 ++ in infixr 6 +++ in a ++ b +++ c",
+            "(infix Left 6 ++ (infix Right 6 +++ (+++ (++ a b) c)))",
+            1,
+        );
+        assert_parse_err("infixl 10 ++ in a", "(infix Left 0 ++ a)", 1);
+        assert_parse_err("infixl 1 in a", "(infix Left 1  a)", 1);
+    }
+
+    #[test]
+    fn test_parse_unicode_idents() {
+        assert_parse("fn 名前 => größe_1 名前", "(fn 名前 (call größe_1 名前))");
+        assert_parse("let caf\u{E9} = 1 in cafe\u{301}", "(let caf\u{E9} 1 caf\u{E9})");
+
+        // A mixed-script name is only reported at its first occurrence.
+        let sm = SourceMap::new();
+        let src = "let p\u{430}ypal = 1 in p\u{430}ypal + paypal";
+        let sf = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let diag = DiagnosticsEngine::new(Rc::new(sm));
+        parse_file(&sf, &diag);
+        let diagnostics = diag.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(!diagnostics[0].is_error());
+        let span = diagnostics[0].primary_span().unwrap();
+        assert_eq!((span.start.to_usize(), span.end.to_usize()), (4, 11));
+    }
+
+    #[test]
+    fn test_parse_compound_exprs() {
+        assert_parse("fn x => x + 1", "(fn x (+ x 1))");
+        assert_parse("if a then b else c", "(if a b c)");
+        assert_parse(
+            "let sayHello = fn name => \"Hello, \" ^ name in sayHello \"Izumi\"",
+            "(let sayHello (fn name (^ \"Hello, \" name)) (call sayHello \"Izumi\"))",
+        );
+    }
+
+    #[test]
+    fn test_parse_decls() {
+        let sm = SourceMap::new();
+        let src = "let id = fn x => x\nlet two = let one = 1 in one + one\nlet x = id two";
+        let sf = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let diag = DiagnosticsEngine::new(Rc::new(sm));
+        let decls = parse_decls(&sf, &diag)
+            .iter()
+            .map(|decl| format!("{} = {}", decl.name.name, dump(&decl.value)))
+            .collect::<Vec<_>>();
+        assert_eq!(diag.err_count(), 0);
+        assert_eq!(decls, [
+            "id = (fn x x)",
+            "two = (let one 1 (+ one one))",
+            "x = (call id two)",
+        ]);
+    }
+
+    #[test]
+    fn test_parse_items() {
+        let parse_item = |src: &str| {
+            let sm = SourceMap::new();
+            let sf = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+            let diag = DiagnosticsEngine::new(Rc::new(sm));
+            let item = match parse_item(&sf, &diag) {
+                Item::Decl(decl) => format!("{} = {}", decl.name.name, dump(&decl.value)),
+                Item::Expr(expr) => dump(&expr),
+            };
+            (item, diag.err_count())
+        };
+        assert_eq!(parse_item("let x = 1 + 2"), ("x = (+ 1 2)".to_string(), 0));
+        assert_eq!(parse_item("let x = 1 in x"), ("(let x 1 x)".to_string(), 0));
+        assert_eq!(parse_item("f x"), ("(call f x)".to_string(), 0));
+        assert_eq!(parse_item("let x = 1 )"), ("x = 1".to_string(), 1));
+    }
+
+    #[test]
+    fn test_parse_group_exprs() {
+        assert_parse("(a; b)", "(block a b)");
+        assert_parse("(a; b;)", "(block a b ())");
+        assert_parse("(;a;;b)", "(block a b)");
+    }
+
+    #[test]
+    fn test_parse_spans() {
+        let (expr, _) = parse("let x = 1 in\nf x");
+        assert_eq!(expr.span, Span::new(Pos::from_usize(0), Pos::from_usize(16)));
+        let ExprKind::Let(_, value, body) = expr.kind else { panic!() };
+        assert_eq!(value.span, Span::new(Pos::from_usize(8), Pos::from_usize(9)));
+        assert_eq!(body.span, Span::new(Pos::from_usize(13), Pos::from_usize(16)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_parse_err("let x = 1", "(let x 1 <error>)", 1);
+        assert_parse_err("if a then b", "(if a b <error>)", 1);
+        assert_parse_err("(a b", "(paren (call a b))", 1);
+        assert_parse_err("f x )", "(call f x)", 1);
+        assert_parse_err("let = 1 in x", "(let  1 x)", 1);
+    }
+
+    #[test]
+    fn test_parse_error_recovery() {
+        // Every mistake is reported once, and the parser goes on.
+        assert_parse_err(
+            "let x = => 1 in if x then ) else (a; =>; b)",
+            "(let x <error> (if x <error> (block a <error> b)))",
+            3,
+        );
+        assert_parse_err(
+            "let f = fn x => x + in f (g; 1 2",
+            "(let f (fn x (+ x <error>)) (call f (block g (call 1 2))))",
+            2,
+        );
+        assert_parse_err(
+            "1 + if a then b else c",
+            "(+ 1 (if a b c))",
+            1,
+        );
+        assert_parse_err("a ) b ) c", "a", 2);
+    }
+
+    #[test]
+    fn test_parse_nesting_limit() {
+        // The test threads have small stacks, parse in a thread like the
+        // driver does.
+        let parse_errors = |src: String| {
+            thread::Builder::new()
+                .stack_size(256 * 1024 * 1024)
+                .spawn(move || {
+                    let sm = SourceMap::new();
+                    let sf = sm.load_virtual_file("test.kona".to_string(), src);
+                    let diag = DiagnosticsEngine::new(Rc::new(sm));
+                    parse_file(&sf, &diag);
+                    diag.take_diagnostics()
+                        .into_iter()
+                        .map(|diagnostic| diagnostic.message)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap()
+                .join()
+                .unwrap()
+        };
+        let parens = |n| format!("{}1{}", "(".repeat(n), ")".repeat(n));
+        let too_deep = vec!["expression is nested too deeply".to_string()];
+
+        // The top-level expression is one level deep.
+        assert!(parse_errors(parens(MAX_NESTING_DEPTH - 1)).is_empty());
+        assert_eq!(parse_errors(parens(MAX_NESTING_DEPTH)), too_deep);
+        assert!(parse_errors(format!("1{}", " + 1".repeat(MAX_NESTING_DEPTH - 1))).is_empty());
+        assert_eq!(parse_errors(format!("1{}", " + 1".repeat(MAX_NESTING_DEPTH))), too_deep);
+        assert_eq!(parse_errors(format!("f{}", " x".repeat(MAX_NESTING_DEPTH))), too_deep);
+        assert_eq!(parse_errors("let x = 1 in ".repeat(MAX_NESTING_DEPTH) + "x"), too_deep);
+    }
+
+    /// Parses the source and returns its concrete syntax tree, which must
+    /// print back to the source.
+    fn parse_cst(src: &str) -> SyntaxNode {
+        let sm = SourceMap::new();
+        let sf = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let diag = DiagnosticsEngine::new(Rc::new(sm));
+        let (_, tree) = parse_file_with_cst(&sf, &diag);
+        diag.take_diagnostics();
+
+        assert_eq!(tree.text(), src);
+        assert_eq!(tree.span(), Span::new(sf.start_pos, sf.end_pos));
+        for node in tree.descendants().skip(1) {
+            let tokens = node.tokens().collect::<Vec<_>>();
+            if let (Some(first), Some(last)) = (tokens.first(), tokens.last()) {
+                assert!(!first.kind().is_trivia(), "{:?} starts with trivia", node);
+                assert!(!last.kind().is_trivia(), "{:?} ends with trivia", node);
+            }
+        }
+        tree
+    }
+
+    #[test]
+    fn test_parse_cst_round_trip() {
+        parse_cst("");
+        parse_cst("let x = 1 in\n  -- The answer.\n  f x /- inline -/ + -2\n");
+        parse_cst("  (a; b;) \r\n");
+        parse_cst("infixl 6 ++ in a ++ b");
+        parse_cst("let x = => 1 in if x then ) else (a; =>; b)");
+        parse_cst("let f = fn x => x + in f (g; 1 2");
+        parse_cst("a ) b ) c  ");
+        parse_cst("f - x");
+        parse_cst("\"unterminated");
+        parse_cst("/- unterminated");
+    }
+
+    #[test]
+    fn test_parse_cst_dump() {
+        let tree = parse_cst("f (x) -- call\n");
+        assert_eq!(dump_cst(&tree), [
+            "Root@0..14",
+            "  CallExpr@0..5",
+            "    NameRef@0..1",
+            "      Ident@0..1 \"f\"",
+            "    Whitespace@1..2 \" \"",
+            "    ParenExpr@2..5",
+            "      LParen@2..3 \"(\"",
+            "      NameRef@3..4",
+            "        Ident@3..4 \"x\"",
+            "      RParen@4..5 \")\"",
+            "  Whitespace@5..6 \" \"",
+            "  LineComment@6..13 \"-- call\"",
+            "  Eol@13..14 \"\\n\"",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        assert_parse("a -- b\n+ c", "(+ a c)");
+        assert_parse("a --> b <-- c --- d", "(<-- (--> a b) c)");
+        assert_parse("a /- b /- c -/ d -/ - c", "(- a c)");
+        assert_parse("f --\rx", "(call f x)");
+
+        let kinds = |src: &str| {
+            crate::lex::tokenize_with_trivia(src, Pos::from_usize(0))
+                .map(|token| token.kind.syntax_kind())
+                .filter(|kind| kind.is_trivia() && *kind != SyntaxKind::Whitespace)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            kinds("-- a\n--- b\n---- c"),
+            [
+                SyntaxKind::LineComment, SyntaxKind::Eol,
+                SyntaxKind::LineDocComment, SyntaxKind::Eol,
+                SyntaxKind::LineComment,
+            ],
+        );
+        assert_eq!(
+            kinds("/- a -/ /-- b -/ /--/ /--- c -/"),
+            [
+                SyntaxKind::BlockComment, SyntaxKind::BlockDocComment,
+                SyntaxKind::BlockComment, SyntaxKind::BlockComment,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_parse_doc_comments() {
+        let sm = SourceMap::new();
+        let src = [
+            "--- The identity.",
+            "---",
+            "--- Returns `x`.",
+            "let id = fn x => x",
+            "",
+            "--- Not the doc of `one`.",
+            "",
+            "/-- One. -/",
+            "let one = 1",
+            "-- Not a doc comment.",
+            "let two = 2",
+        ].join("\n");
+        let sf = sm.load_virtual_file("test.kona".to_string(), src);
+        let diag = DiagnosticsEngine::new(Rc::new(sm));
+        let mut parser = Parser::new(&sf, &diag);
+        parser.parse_decls();
+        let root = ast::Root::cast(parser.finish_syntax_tree()).unwrap();
+        assert_eq!(diag.err_count(), 0);
+
+        let docs = root.decls().map(|decl| decl.doc_comment()).collect::<Vec<_>>();
+        assert_eq!(docs, [
+            Some("The identity.\n\nReturns `x`.".to_string()),
+            Some("One.".to_string()),
+            None,
+        ]);
+
+        let tree = parse_cst("let x = 1 in\n--- The answer.\nlet y = 42 in y");
+        let Some(ast::Expr::Let(outer)) = ast::Root::cast(tree).unwrap().expr() else { panic!() };
+        let Some(ast::Expr::Let(inner)) = outer.body() else { panic!() };
+        assert_eq!(outer.doc_comment(), None);
+        assert_eq!(inner.doc_comment().as_deref(), Some("The answer."));
+    }
+
+    #[test]
+    fn test_parse_cst_typed_view() {
+        let tree = parse_cst("let x = f 1 in\n-2 + x -- sum");
+        let root = ast::Root::cast(tree).unwrap();
+        let Some(ast::Expr::Let(let_expr)) = root.expr() else { panic!() };
+        assert_eq!(let_expr.name().unwrap().text(), "x");
+
+        let Some(ast::Expr::Call(call)) = let_expr.value() else { panic!() };
+        assert_eq!(call.func().unwrap().syntax().text(), "f");
+        assert_eq!(call.args().count(), 1);
+
+        let Some(ast::Expr::Infix(infix)) = let_expr.body() else { panic!() };
+        let ops = infix.operators().map(|op| op.text().to_string()).collect::<Vec<_>>();
+        assert_eq!(ops, ["+"]);
+        let Some(ast::Expr::Lit(lit)) = infix.operands().next() else { panic!() };
+        assert!(lit.is_negative());
+        assert_eq!(lit.token().unwrap().text(), "2");
+        let in_kw = let_expr.in_kw().unwrap();
+        assert_eq!((let_expr.eq_token().unwrap().text(), in_kw.text()), ("=", "in"));
+        let before_body = let_expr.body().unwrap().syntax().prev_siblings_with_tokens();
+        assert_eq!(before_body.map(|element| element.kind()).take(2).collect::<Vec<_>>(), [
+            SyntaxKind::Eol, SyntaxKind::InKw,
+        ]);
+
+        // A missing expression is an empty error node.
+        let tree = parse_cst("let x = 1");
+        let root = ast::Root::cast(tree).unwrap();
+        let Some(ast::Expr::Let(let_expr)) = root.expr() else { panic!() };
+        let Some(ast::Expr::Error(error)) = let_expr.body() else { panic!() };
+        assert!(error.syntax().text().is_empty());
     }
 }
@@ -45,7 +45,7 @@ pub fn analyze(
         } else if byte >= 127 {
             // This is either ASCII control character "DEL" or the beginning of
             // a multibyte char. Just decode to `char`.
-            let char = (&src[idx..]).chars().next().unwrap();
+            let char = src[idx..].chars().next().unwrap();
             char_len = char.len_utf8();
 
             let pos = Pos::from_usize(idx + offset);
@@ -66,10 +66,10 @@ pub fn analyze(
 
     // The code above optimistically registers a new line after each newline
     // it encounters. If that point is already outside the source file, remove
-    // it again.
+    // it again. An empty file still has one empty line.
     if let Some(&last_line_start) = lines.last() {
         let end_pos = Pos::from_usize(src.len() + offset);
-        if last_line_start == end_pos {
+        if last_line_start == end_pos && lines.len() > 1 {
             lines.pop();
         }
     }
@@ -123,5 +123,11 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_line_analyze_empty() {
+        let (lines, _, _) = analyze("", Pos::from_usize(7));
+        assert_eq!(lines, vec![Pos::from_usize(7)]);
+    }
+
     // TODO: Test multi-byte characters and non-narrow characters.
 }
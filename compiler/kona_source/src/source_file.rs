@@ -1,7 +1,9 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use std::{path::{PathBuf, Component, Prefix, Path}, rc::Rc, ops::Range, fmt};
+use std::{path::{PathBuf, Path}, rc::Rc, ops::Range, fmt};
+#[cfg(windows)]
+use std::path::{Component, Prefix};
 
 use crate::{pos::Pos, source_analyzer};
 
@@ -118,6 +120,20 @@ impl SourceFile {
         }
     }
 
+    /// Returns the text of the line at the given line index, without the line
+    /// break.
+    pub fn line_text(&self, line_index: usize) -> &str {
+        let bounds = self.lookup_line_bounds(line_index);
+        let start = bounds.start.to_usize() - self.start_pos.to_usize();
+        let end = bounds.end.to_usize() - self.start_pos.to_usize();
+        self.src[start..end].trim_end_matches(['\n', '\r'])
+    }
+
+    /// Returns the number of lines in this file, an empty file has one line.
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
 
     /// Looks up the file's 1-based line number and 0-based column offset, for a
     /// given [`Pos`].
@@ -166,6 +182,71 @@ impl SourceFile {
         (line, col, col_display)
     }
 
+    /// Looks up the 0-based line index and the 0-based column in UTF-16 code
+    /// units for a given [`Pos`], the positions used by the Language Server
+    /// Protocol.
+    ///
+    /// A character takes two UTF-16 code units if it takes four bytes in
+    /// UTF-8, i.e. it is outside the Basic Multilingual Plane, and one code
+    /// unit otherwise.
+    pub fn lookup_line_and_utf16_col(&self, pos: Pos) -> (usize, usize) {
+        let Some(line) = self.lookup_line(pos) else {
+            return (0, 0);
+        };
+        let line_start = self.lines[line];
+        let start_idx = self.multi_byte_chars
+            .binary_search_by_key(&line_start, |x| x.pos())
+            .unwrap_or_else(|x| x);
+        let extra_units = self
+            .multi_byte_chars[start_idx..]
+            .iter()
+            .take_while(|x| x.pos() < pos)
+            .map(|x| x.len() as usize - x.utf16_len())
+            .sum::<usize>();
+        (line, pos.to_usize() - line_start.to_usize() - extra_units)
+    }
+
+    /// Finds the [`Pos`] of a 0-based line index and a 0-based column in
+    /// UTF-16 code units, the inverse of [`lookup_line_and_utf16_col`].
+    ///
+    /// A column past the end of the line is clamped to the end of the line,
+    /// before the line break, and a line past the end of the file is clamped
+    /// to the end of the file. A column in the middle of a surrogate pair is
+    /// moved to the start of the character.
+    ///
+    /// [`lookup_line_and_utf16_col`]: SourceFile::lookup_line_and_utf16_col
+    pub fn lookup_pos_by_utf16(&self, line_index: usize, col: usize) -> Pos {
+        if line_index >= self.line_count() {
+            return self.end_pos;
+        }
+        let line_start = self.lookup_line_bounds(line_index).start;
+        let line_end = line_start + self.line_text(line_index).len();
+        let start_idx = self.multi_byte_chars
+            .binary_search_by_key(&line_start, |x| x.pos())
+            .unwrap_or_else(|x| x);
+
+        let mut pos = line_start;
+        let mut units = col;
+        for mbc in self.multi_byte_chars[start_idx..]
+            .iter()
+            .take_while(|x| x.pos() < line_end)
+        {
+            // The characters between two multi-byte characters are all ASCII,
+            // one byte and one code unit each.
+            let gap = mbc.pos().to_usize() - pos.to_usize();
+            if units < gap {
+                return pos + units;
+            }
+            units -= gap;
+            if units < mbc.utf16_len() {
+                return mbc.pos();
+            }
+            units -= mbc.utf16_len();
+            pos = mbc.pos() + mbc.len() as usize;
+        }
+        pos + units.min(line_end.to_usize() - pos.to_usize())
+    }
+
     #[inline]
     pub fn contains(&self, pos: Pos) -> bool {
         pos >= self.start_pos && pos <= self.end_pos
@@ -276,6 +357,7 @@ impl MultiByteChar {
 
     /// Returns the UTF-8 length of this character.
     #[inline]
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> u8 {
         self.len
     }
@@ -285,6 +367,13 @@ impl MultiByteChar {
     pub fn pos(&self) -> Pos {
         self.pos
     }
+
+    /// Returns the UTF-16 length of this character, 2 for a character encoded
+    /// as a surrogate pair, and 1 otherwise.
+    #[inline]
+    pub fn utf16_len(&self) -> usize {
+        if self.len == 4 { 2 } else { 1 }
+    }
 }
 
 /// Represents a non-narrow character in the source code.
@@ -1,7 +1,10 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use std::{rc::Rc, collections::HashMap, path::PathBuf, io, fs, ops::Range};
+use std::{
+    cell::{Cell, RefCell}, rc::Rc, collections::HashMap, path::PathBuf, io, fs,
+    ops::Range,
+};
 
 use crate::{source_file::{SourceFile, FilePath}, pos::Pos, pos_info::PosInfo};
 
@@ -14,45 +17,58 @@ use crate::{source_file::{SourceFile, FilePath}, pos::Pos, pos_info::PosInfo};
 /// unique position [`Pos`] for each byte in the source code. You can get a
 /// human-readable information [`PosInfo`] with a [`Pos`], or read a span of
 /// source code with a [`Range<Pos>`] in source map.
+///
+/// Files can be added to a shared source map, e.g. the REPL loads every input
+/// as a new file into the source map of the session.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SourceMap {
     /// The used position index, for allocating individual position intervals to
     /// source files.
-    used_pos_space: usize,
+    used_pos_space: Cell<usize>,
 
     // WARNING: Don't modify `used_pos_space` directly. Don't add new functions
     // that might modify or access it. `allocate_pos_space` should be the
     // only function that can increase `used_pos_space`.
 
     /// The source files.
-    files: Vec<Rc<SourceFile>>,
+    files: RefCell<Vec<Rc<SourceFile>>>,
 
     /// The source files hash map.
-    files_map: HashMap<FilePath, Rc<SourceFile>>,
+    files_map: RefCell<HashMap<FilePath, Rc<SourceFile>>>,
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        SourceMap::new()
+    }
 }
 
 impl SourceMap {
     pub fn new() -> Self {
         SourceMap {
-            used_pos_space: 0,
-            files: Vec::new(),
-            files_map: HashMap::new(),
+            used_pos_space: Cell::new(0),
+            files: RefCell::new(Vec::new()),
+            files_map: RefCell::new(HashMap::new()),
         }
     }
 
-    fn allocate_pos_space(&mut self, size: usize) -> usize {
-        let pos = self.used_pos_space;
-        self.used_pos_space += size;
+    fn allocate_pos_space(&self, size: usize) -> usize {
+        let pos = self.used_pos_space.get();
+
+        // Leave one extra position after each file, so that the end of file
+        // position (e.g. of an "unexpected end of file" error) is not the
+        // start position of the next file.
+        self.used_pos_space.set(pos + size + 1);
         pos
     }
 
     /// Loads source file from the given path.
     pub fn load_file(
-        &mut self, path: PathBuf,
+        &self, path: PathBuf,
     ) -> io::Result<Rc<SourceFile>> {
         // Path must be absolute to uniquely identify the source file.
         let file_path = FilePath::LocalFile(fs::canonicalize(&path)?);
-        if let Some(sf) = self.files_map.get(&file_path) {
+        if let Some(sf) = self.files_map.borrow().get(&file_path) {
             return Ok(sf.clone());
         }
 
@@ -61,17 +77,17 @@ impl SourceMap {
         let file = Rc::new(
             SourceFile::new(file_path.clone(), Rc::new(src), start_pos)
         );
-        self.files.push(file.clone());
-        self.files_map.insert(file_path, file.clone());
+        self.files.borrow_mut().push(file.clone());
+        self.files_map.borrow_mut().insert(file_path, file.clone());
         Ok(file)
     }
 
     /// Adds a virtual source file with the given name and source string.
     pub fn load_virtual_file(
-        &mut self, name: String, src: String
+        &self, name: String, src: String
     ) -> Rc<SourceFile> {
         let path = FilePath::Virtual(name);
-        if let Some(sf) = self.files_map.get(&path) {
+        if let Some(sf) = self.files_map.borrow().get(&path) {
             return sf.clone();
         }
 
@@ -81,8 +97,8 @@ impl SourceMap {
             Rc::new(src),
             start_pos,
         ));
-        self.files.push(file.clone());
-        self.files_map.insert(path, file.clone());
+        self.files.borrow_mut().push(file.clone());
+        self.files_map.borrow_mut().insert(path, file.clone());
         file
     }
 
@@ -94,10 +110,11 @@ impl SourceMap {
 
     /// Finds the source file containing the given position.
     pub fn lookup_file(&self, pos: Pos) -> Rc<SourceFile> {
-        let idx = self.files
+        let files = self.files.borrow();
+        let idx = files
             .binary_search_by_key(&pos, |file| file.start_pos)
             .unwrap_or_else(|p| p - 1);
-        self.files[idx].clone()
+        files[idx].clone()
     }
 
     /// Returns the source file at the given interval.
@@ -117,7 +134,7 @@ mod tests {
 
     #[test]
     fn test_loc_single_file1() {
-        let mut mgr = SourceMap::new();
+        let mgr = SourceMap::new();
         mgr.load_virtual_file(
             "example.scm".to_string(),
             "abcdefghi".to_string(),
@@ -131,7 +148,7 @@ mod tests {
 
     #[test]
     fn test_loc_single_file2() {
-        let mut mgr = SourceMap::new();
+        let mgr = SourceMap::new();
         mgr.load_virtual_file(
             "example".to_string(),
             "abc\ndef\nghi".to_string(),
@@ -145,7 +162,7 @@ mod tests {
 
     #[test]
     fn test_loc_single_file3() {
-        let mut mgr = SourceMap::new();
+        let mgr = SourceMap::new();
         mgr.load_virtual_file(
             "example".to_string(),
             "🌊🌊🌊\n🌊🌊🌊\n🌊🌊🌊".to_string(),
@@ -157,9 +174,47 @@ mod tests {
         assert_eq!(loc.col_display, 2);
     }
 
+    #[test]
+    fn test_loc_end_of_file() {
+        let mgr = SourceMap::new();
+        let a = mgr.load_virtual_file("a".to_string(), "ab".to_string());
+        let b = mgr.load_virtual_file("b".to_string(), "".to_string());
+
+        let loc = mgr.lookup_pos_info(a.end_pos);
+        assert_eq!(loc.file.file_name(), "a");
+        assert_eq!((loc.line, loc.col), (1, 2));
+
+        let loc = mgr.lookup_pos_info(b.end_pos);
+        assert_eq!(loc.file.file_name(), "b");
+        assert_eq!((loc.line, loc.col), (1, 0));
+    }
+
+    #[test]
+    fn test_utf16_col() {
+        let mgr = SourceMap::new();
+        let file = mgr.load_virtual_file(
+            "example".to_string(),
+            "ab\n🌊é🌊x\r\nyz".to_string(),
+        );
+        let line_start = file.start_pos + 3;
+
+        // `🌊` is 4 bytes and 2 code units, `é` is 2 bytes and 1 code unit.
+        for (offset, col) in [(0, 0), (4, 2), (6, 3), (10, 5), (11, 6)] {
+            let pos = line_start + offset;
+            assert_eq!(file.lookup_line_and_utf16_col(pos), (1, col));
+            assert_eq!(file.lookup_pos_by_utf16(1, col), pos);
+        }
+        assert_eq!(file.lookup_line_and_utf16_col(file.end_pos), (2, 2));
+
+        // The middle of a surrogate pair, and past the end of the line.
+        assert_eq!(file.lookup_pos_by_utf16(1, 1), line_start);
+        assert_eq!(file.lookup_pos_by_utf16(1, 100), line_start + 11);
+        assert_eq!(file.lookup_pos_by_utf16(5, 0), file.end_pos);
+    }
+
     #[test]
     fn test_lookup_source() {
-        let mut mgr = SourceMap::new();
+        let mgr = SourceMap::new();
         mgr.load_virtual_file(
             "example".to_string(),
             "abcdefghijklmn".to_string(),
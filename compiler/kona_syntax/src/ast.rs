@@ -0,0 +1,421 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! A typed view over the concrete syntax tree.
+//!
+//! Every type here wraps a [`SyntaxNode`] of the matching kind, and finds its
+//! parts among the children of the node. The parser inserts an [`Error`] node
+//! for every missing expression, so an accessor only returns `None` if the
+//! tree is not built by the parser, or a token is missing, e.g. the name in
+//! `let = 1 in x`.
+
+use crate::{
+    kind::SyntaxKind,
+    node::{SyntaxNode, SyntaxToken},
+};
+
+/// A typed node which can be converted from and to a [`SyntaxNode`].
+pub trait AstNode: Sized {
+    fn can_cast(kind: SyntaxKind) -> bool;
+
+    /// Returns `None` if the node is not of this type.
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! ast_node {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name(SyntaxNode);
+
+        impl AstNode for $name {
+            fn can_cast(kind: SyntaxKind) -> bool {
+                kind == SyntaxKind::$name
+            }
+
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                Self::can_cast(node.kind()).then_some($name(node))
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                &self.0
+            }
+        }
+    };
+}
+
+ast_node! {
+    /// The whole file, an expression or a sequence of declarations.
+    Root
+}
+ast_node! {
+    /// `let name = value`
+    Decl
+}
+ast_node! {
+    /// The tokens skipped by the parser, or a missing expression.
+    Error
+}
+ast_node! {
+    /// `()`
+    UnitExpr
+}
+ast_node! {
+    /// A literal, with the `-` of a negative number.
+    LitExpr
+}
+ast_node! {
+    /// An identifier used as an expression.
+    NameRef
+}
+ast_node! {
+    /// `(expr)`
+    ParenExpr
+}
+ast_node! {
+    /// `(expr; expr; ...)`
+    BlockExpr
+}
+ast_node! {
+    /// `func arg1 arg2 ...`
+    CallExpr
+}
+ast_node! {
+    /// `operand op operand op ...`, the operators are not resolved.
+    InfixExpr
+}
+ast_node! {
+    /// `fn param => body`
+    FnExpr
+}
+ast_node! {
+    /// `if condition then then_branch else else_branch`
+    IfExpr
+}
+ast_node! {
+    /// `let name = value in body`
+    LetExpr
+}
+ast_node! {
+    /// `infixl 6 op1 op2 ... in body`
+    FixityExpr
+}
+
+/// Any expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Error(Error),
+    Unit(UnitExpr),
+    Lit(LitExpr),
+    NameRef(NameRef),
+    Paren(ParenExpr),
+    Block(BlockExpr),
+    Call(CallExpr),
+    Infix(InfixExpr),
+    Fn(FnExpr),
+    If(IfExpr),
+    Let(LetExpr),
+    Fixity(FixityExpr),
+}
+
+impl AstNode for Expr {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        matches!(
+            kind,
+            SyntaxKind::Error | SyntaxKind::UnitExpr | SyntaxKind::LitExpr
+                | SyntaxKind::NameRef | SyntaxKind::ParenExpr
+                | SyntaxKind::BlockExpr | SyntaxKind::CallExpr
+                | SyntaxKind::InfixExpr | SyntaxKind::FnExpr
+                | SyntaxKind::IfExpr | SyntaxKind::LetExpr
+                | SyntaxKind::FixityExpr
+        )
+    }
+
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        let expr = match node.kind() {
+            SyntaxKind::Error => Expr::Error(Error(node)),
+            SyntaxKind::UnitExpr => Expr::Unit(UnitExpr(node)),
+            SyntaxKind::LitExpr => Expr::Lit(LitExpr(node)),
+            SyntaxKind::NameRef => Expr::NameRef(NameRef(node)),
+            SyntaxKind::ParenExpr => Expr::Paren(ParenExpr(node)),
+            SyntaxKind::BlockExpr => Expr::Block(BlockExpr(node)),
+            SyntaxKind::CallExpr => Expr::Call(CallExpr(node)),
+            SyntaxKind::InfixExpr => Expr::Infix(InfixExpr(node)),
+            SyntaxKind::FnExpr => Expr::Fn(FnExpr(node)),
+            SyntaxKind::IfExpr => Expr::If(IfExpr(node)),
+            SyntaxKind::LetExpr => Expr::Let(LetExpr(node)),
+            SyntaxKind::FixityExpr => Expr::Fixity(FixityExpr(node)),
+            _ => return None,
+        };
+        Some(expr)
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expr::Error(expr) => expr.syntax(),
+            Expr::Unit(expr) => expr.syntax(),
+            Expr::Lit(expr) => expr.syntax(),
+            Expr::NameRef(expr) => expr.syntax(),
+            Expr::Paren(expr) => expr.syntax(),
+            Expr::Block(expr) => expr.syntax(),
+            Expr::Call(expr) => expr.syntax(),
+            Expr::Infix(expr) => expr.syntax(),
+            Expr::Fn(expr) => expr.syntax(),
+            Expr::If(expr) => expr.syntax(),
+            Expr::Let(expr) => expr.syntax(),
+            Expr::Fixity(expr) => expr.syntax(),
+        }
+    }
+}
+
+/// A node which can be documented by the doc comments before it, i.e. a
+/// `let` binding.
+///
+/// ```text
+/// --- Returns the successor of `n`.
+/// let succ = fn n => n + 1
+/// ```
+pub trait DocCommentOwner: AstNode {
+    /// Returns the text of the doc comments right before the node, without
+    /// the comment markers. The comments must be separated from the node and
+    /// from each other only by line breaks, an empty line or another comment
+    /// ends the documentation.
+    fn doc_comment(&self) -> Option<String> {
+        let mut comments = Vec::new();
+        let mut line_breaks = 0;
+        for element in self.syntax().prev_siblings_with_tokens() {
+            let Some(token) = element.into_token() else { break };
+            match token.kind() {
+                SyntaxKind::Whitespace => {}
+                SyntaxKind::Eol if line_breaks == 0 => line_breaks += 1,
+                kind if kind.is_doc_comment() => {
+                    comments.push(token);
+                    line_breaks = 0;
+                }
+                _ => break,
+            }
+        }
+
+        if comments.is_empty() {
+            return None;
+        }
+        let lines = comments.iter().rev().map(|comment| {
+            let text = comment.text();
+            if comment.kind() == SyntaxKind::LineDocComment {
+                let text = text.trim_start_matches('-');
+                text.strip_prefix(' ').unwrap_or(text).trim_end()
+            } else {
+                let text = text.strip_prefix("/--").unwrap_or(text);
+                text.strip_suffix("-/").unwrap_or(text).trim()
+            }
+        });
+        Some(lines.collect::<Vec<_>>().join("\n"))
+    }
+}
+
+impl DocCommentOwner for Decl {}
+impl DocCommentOwner for LetExpr {}
+
+impl Root {
+    /// Returns the top-level expression of a file parsed as an expression.
+    pub fn expr(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    /// Returns the declarations of a file parsed as declarations.
+    pub fn decls(&self) -> impl Iterator<Item = Decl> {
+        children(&self.0)
+    }
+}
+
+impl Decl {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    pub fn eq_token(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Eq)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+}
+
+impl LitExpr {
+    /// Returns the literal token, without the `-` of a negative number.
+    pub fn token(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().find(|token| {
+            matches!(
+                token.kind(),
+                SyntaxKind::IntLit | SyntaxKind::FloatLit
+                    | SyntaxKind::BoolLit | SyntaxKind::StringLit
+                    | SyntaxKind::CharLit
+            )
+        })
+    }
+
+    /// Returns true if the literal is a negative number, e.g. `-1`.
+    pub fn is_negative(&self) -> bool {
+        token(&self.0, SyntaxKind::Op).is_some()
+    }
+}
+
+impl UnitExpr {
+    pub fn r_paren(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::RParen)
+    }
+}
+
+impl NameRef {
+    pub fn ident(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+}
+
+impl ParenExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn r_paren(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::RParen)
+    }
+}
+
+impl BlockExpr {
+    /// Returns the expressions between the `;`s, the unit expression implied
+    /// by a trailing `;` is not in the tree.
+    pub fn exprs(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0)
+    }
+
+    pub fn r_paren(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::RParen)
+    }
+}
+
+impl CallExpr {
+    pub fn func(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0).skip(1)
+    }
+}
+
+impl InfixExpr {
+    pub fn operands(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0)
+    }
+
+    /// Returns the operators in the source order, `=` is an operator too.
+    pub fn operators(&self) -> impl Iterator<Item = SyntaxToken> {
+        operators(&self.0)
+    }
+}
+
+impl FnExpr {
+    pub fn param(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    /// Returns the `=>` after the parameter.
+    pub fn arrow(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::DArrow)
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+}
+
+impl IfExpr {
+    pub fn condition(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn then_branch(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+
+    pub fn else_branch(&self) -> Option<Expr> {
+        children(&self.0).nth(2)
+    }
+
+    pub fn then_kw(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::ThenKw)
+    }
+
+    pub fn else_kw(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::ElseKw)
+    }
+}
+
+impl LetExpr {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+
+    pub fn eq_token(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Eq)
+    }
+
+    pub fn in_kw(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::InKw)
+    }
+}
+
+impl FixityExpr {
+    /// Returns the `infix`, `infixl` or `infixr` keyword.
+    pub fn fixity(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().find(|token| {
+            matches!(
+                token.kind(),
+                SyntaxKind::InfixKw | SyntaxKind::InfixlKw | SyntaxKind::InfixrKw
+            )
+        })
+    }
+
+    /// Returns the precedence, `None` if it is omitted.
+    pub fn precedence(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::IntLit)
+    }
+
+    pub fn operators(&self) -> impl Iterator<Item = SyntaxToken> {
+        operators(&self.0)
+    }
+
+    pub fn in_kw(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::InKw)
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+}
+
+fn children<N: AstNode>(parent: &SyntaxNode) -> impl Iterator<Item = N> {
+    parent.children().filter_map(N::cast)
+}
+
+fn token(parent: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    parent.child_tokens().find(|token| token.kind() == kind)
+}
+
+fn operators(parent: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    parent
+        .child_tokens()
+        .filter(|token| matches!(token.kind(), SyntaxKind::Op | SyntaxKind::Eq))
+}
@@ -0,0 +1,42 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Dumps the CST as an indented tree for debugging, e.g. in
+//! `kona parse --emit=cst`.
+
+use std::fmt::Write;
+
+use crate::node::{SyntaxElement, SyntaxNode};
+
+/// Dumps a node, one node or token per line, the children are indented under
+/// their parent. The offsets are relative to the start of `node`.
+///
+/// ```text
+/// Root@0..10
+///   NameRef@0..1
+///     Ident@0..1 "f"
+///   Whitespace@1..2 " "
+///   ...
+/// ```
+pub fn dump_cst(node: &SyntaxNode) -> String {
+    let mut out = String::new();
+    let base = node.span().start.to_usize();
+    write_element(&mut out, SyntaxElement::Node(node.clone()), base, 0);
+    out
+}
+
+fn write_element(out: &mut String, element: SyntaxElement, base: usize, depth: usize) {
+    let span = element.span();
+    let (start, end) = (span.start.to_usize() - base, span.end.to_usize() - base);
+    write!(out, "{:indent$}{:?}@{}..{}", "", element.kind(), start, end, indent = depth * 2)
+        .unwrap();
+    match element {
+        SyntaxElement::Node(node) => {
+            out.push('\n');
+            for child in node.children_with_tokens() {
+                write_element(out, child, base, depth + 1);
+            }
+        }
+        SyntaxElement::Token(token) => writeln!(out, " {:?}", token.text()).unwrap(),
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The green tree, the immutable storage of the concrete syntax tree.
+//!
+//! A green node only knows its kind, its children and its length in bytes,
+//! not its position or parent, so it is cheap to clone and share. Use
+//! [`SyntaxNode`](crate::node::SyntaxNode) to navigate the tree.
+
+use std::rc::Rc;
+
+use crate::kind::SyntaxKind;
+
+/// An immutable node of the green tree.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenNode(Rc<GreenNodeData>);
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct GreenNodeData {
+    kind: SyntaxKind,
+    len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let len = children.iter().map(GreenElement::len).sum();
+        GreenNode(Rc::new(GreenNodeData { kind, len, children }))
+    }
+
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+
+    /// Returns the length of the text of this node in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    #[inline]
+    pub fn children(&self) -> &[GreenElement] {
+        &self.0.children
+    }
+
+    /// Returns true if both are the same node, not only equal.
+    #[inline]
+    pub fn ptr_eq(&self, other: &GreenNode) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the text of this node, i.e. the text of all tokens in it.
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.len());
+        self.write_text(&mut text);
+        text
+    }
+
+    fn write_text(&self, out: &mut String) {
+        for child in self.children() {
+            match child {
+                GreenElement::Node(node) => node.write_text(out),
+                GreenElement::Token(token) => out.push_str(token.text()),
+            }
+        }
+    }
+}
+
+/// An immutable token of the green tree.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenToken(Rc<GreenTokenData>);
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct GreenTokenData {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: impl Into<String>) -> GreenToken {
+        GreenToken(Rc::new(GreenTokenData { kind, text: text.into() }))
+    }
+
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.text.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.text.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+
+impl GreenElement {
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind(),
+            GreenElement::Token(token) => token.kind(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len(),
+            GreenElement::Token(token) => token.len(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Builds a green tree from the tokens in the source order.
+///
+/// ```
+/// # use kona_syntax::{green::GreenNodeBuilder, kind::SyntaxKind};
+/// let mut builder = GreenNodeBuilder::new();
+/// builder.start_node(SyntaxKind::Root);
+/// let checkpoint = builder.checkpoint();
+/// builder.token(SyntaxKind::IntLit, "1");
+/// // `1` turns out to be the first operand of an infix expression.
+/// builder.start_node_at(checkpoint, SyntaxKind::InfixExpr);
+/// builder.token(SyntaxKind::Op, "+");
+/// builder.token(SyntaxKind::IntLit, "2");
+/// builder.finish_node();
+/// builder.finish_node();
+/// assert_eq!(builder.finish().text(), "1+2");
+/// ```
+#[derive(Default)]
+pub struct GreenNodeBuilder {
+    /// The kinds of the unfinished nodes, and the index of their first child
+    /// in `children`.
+    parents: Vec<(SyntaxKind, usize)>,
+
+    /// The children of the unfinished nodes, the finished nodes are moved into
+    /// their parents.
+    children: Vec<GreenElement>,
+}
+
+/// A position in the builder, where a node can be started later with
+/// [`GreenNodeBuilder::start_node_at`].
+#[derive(Clone, Copy, Debug)]
+pub struct Checkpoint(usize);
+
+impl GreenNodeBuilder {
+    pub fn new() -> GreenNodeBuilder {
+        GreenNodeBuilder::default()
+    }
+
+    /// Starts a new node, the following tokens and nodes are its children
+    /// until [`finish_node`](GreenNodeBuilder::finish_node).
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    /// Finishes the current node.
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("no node to finish");
+        let children = self.children.drain(first_child..).collect();
+        self.children.push(GreenElement::Node(GreenNode::new(kind, children)));
+    }
+
+    pub fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.children.push(GreenElement::Token(GreenToken::new(kind, text)));
+    }
+
+    /// Remembers the current position, a node started at the checkpoint later
+    /// wraps everything added after it.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    /// Starts a node at the checkpoint, the nodes and tokens added since then
+    /// become its children, except the trivia right after the checkpoint,
+    /// which stays in the parent.
+    pub fn start_node_at(&mut self, Checkpoint(index): Checkpoint, kind: SyntaxKind) {
+        assert!(index <= self.children.len(), "checkpoint is no longer valid");
+        if let Some(&(_, first_child)) = self.parents.last() {
+            assert!(index >= first_child, "checkpoint is outside the current node");
+        }
+        let leading_trivia = self.children[index..]
+            .iter()
+            .take_while(|child| child.kind().is_trivia())
+            .count();
+        self.parents.push((kind, index + leading_trivia));
+    }
+
+    /// Returns the root node, all nodes must be finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one root node.
+    pub fn finish(mut self) -> GreenNode {
+        assert!(self.parents.is_empty(), "unfinished nodes");
+        match (self.children.pop(), self.children.is_empty()) {
+            (Some(GreenElement::Node(root)), true) => root,
+            _ => panic!("expected exactly one root node"),
+        }
+    }
+}
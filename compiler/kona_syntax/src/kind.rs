@@ -0,0 +1,100 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+/// The kind of a token or a node in the concrete syntax tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SyntaxKind {
+    // Punctuation.
+    LParen,
+    RParen,
+    Semi,
+    Eq,
+    DArrow,
+
+    // Keywords.
+    ElseKw,
+    FnKw,
+    IfKw,
+    InKw,
+    InfixKw,
+    InfixlKw,
+    InfixrKw,
+    LetKw,
+    ThenKw,
+
+    Ident,
+    Op,
+    IntLit,
+    FloatLit,
+    BoolLit,
+    StringLit,
+    CharLit,
+
+    // Trivia.
+    Whitespace,
+    Eol,
+    LineComment,
+    BlockComment,
+    LineDocComment,
+    BlockDocComment,
+
+    /// A character which can't start any token.
+    Invalid,
+
+    /// The whole file.
+    Root,
+
+    /// The tokens skipped by the parser at a syntax error.
+    Error,
+
+    /// A top-level declaration `let x = e`, without `in`.
+    Decl,
+
+    UnitExpr,
+    LitExpr,
+
+    /// An identifier used as an expression.
+    NameRef,
+    ParenExpr,
+    BlockExpr,
+    CallExpr,
+
+    /// A sequence of operands and infix operators, the operators are not
+    /// resolved in the syntax tree.
+    InfixExpr,
+    FnExpr,
+    IfExpr,
+    LetExpr,
+    FixityExpr,
+}
+
+impl SyntaxKind {
+    /// Returns true for whitespace, line breaks and comments.
+    #[inline]
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Whitespace | SyntaxKind::Eol
+                | SyntaxKind::LineComment | SyntaxKind::BlockComment
+                | SyntaxKind::LineDocComment | SyntaxKind::BlockDocComment
+        )
+    }
+
+    /// Returns true for all comments, including the doc comments.
+    #[inline]
+    pub fn is_comment(self) -> bool {
+        self.is_trivia() && !matches!(self, SyntaxKind::Whitespace | SyntaxKind::Eol)
+    }
+
+    /// Returns true for the doc comments, `--- ...` and `/-- ... -/`.
+    #[inline]
+    pub fn is_doc_comment(self) -> bool {
+        matches!(self, SyntaxKind::LineDocComment | SyntaxKind::BlockDocComment)
+    }
+
+    /// Returns true if this is the kind of a token, not a node.
+    #[inline]
+    pub fn is_token(self) -> bool {
+        self <= SyntaxKind::Invalid
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The lossless concrete syntax tree (CST) of Kona.
+//!
+//! Every byte of the source, including whitespace and comments, belongs to a
+//! token in the tree, so the tree prints back to the exact source. The tree
+//! is built in two layers, like in Roslyn and rust-analyzer:
+//!
+//! - The [green tree](green) is immutable, it only knows the kinds and the
+//!   text of the nodes, so identical subtrees can be shared;
+//! - The [red tree](node) is a cursor over the green tree, built on demand,
+//!   which knows the parents and the positions of the nodes.
+//!
+//! The [`ast`] module is a typed view over the red tree, e.g. a `LetExpr`
+//! with its name, value and body. The CST is built by the parser in
+//! `kona_parse` along with the AST, and the formatter in `kona_fmt` prints
+//! from it.
+
+pub mod ast;
+pub mod dump;
+pub mod green;
+pub mod kind;
+pub mod node;
@@ -0,0 +1,367 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! The red tree, a cursor over the green tree.
+//!
+//! A [`SyntaxNode`] is created on demand when the tree is navigated, it knows
+//! its parent and its position in the source map, so its [`Span`] can be used
+//! in diagnostics like the spans of the AST.
+
+use std::{fmt, iter, rc::Rc};
+
+use kona_source::{pos::Pos, span::Span};
+
+use crate::{green::{GreenElement, GreenNode, GreenToken}, kind::SyntaxKind};
+
+/// A node of the concrete syntax tree.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: GreenNode,
+    parent: Option<SyntaxNode>,
+
+    /// The index of this node in the children of its parent.
+    index: usize,
+
+    /// The position of the first byte of the node.
+    start: Pos,
+}
+
+impl SyntaxNode {
+    /// Creates the root of a tree, `start` is the start position of the source
+    /// file, see [`SourceFile::start_pos`].
+    ///
+    /// [`SourceFile::start_pos`]: kona_source::source_file::SourceFile::start_pos
+    pub fn new_root(green: GreenNode, start: Pos) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData { green, parent: None, index: 0, start }))
+    }
+
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    #[inline]
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    #[inline]
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// Returns the parent, the parent of the parent, and so on, starting from
+    /// this node itself.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    #[inline]
+    pub fn span(&self) -> Span {
+        Span::new(self.0.start, self.0.start + self.green().len())
+    }
+
+    /// Returns the source text of this node, including the trivia in it.
+    pub fn text(&self) -> String {
+        self.green().text()
+    }
+
+    /// Returns the child nodes and tokens in the source order.
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        let parent = self.clone();
+        let mut start = self.0.start;
+        let children = self.green().children().to_vec();
+        children.into_iter().enumerate().map(move |(index, child)| {
+            let child_start = start;
+            start += child.len();
+            parent.child(index, child, child_start)
+        })
+    }
+
+    /// Returns the nodes and tokens before this node in its parent, the
+    /// nearest one first.
+    pub fn prev_siblings_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        prev_siblings(self.parent(), self.0.index, self.0.start)
+    }
+
+    /// Creates the red element for a green child of this node.
+    fn child(&self, index: usize, green: GreenElement, start: Pos) -> SyntaxElement {
+        match green {
+            GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                green,
+                parent: Some(self.clone()),
+                index,
+                start,
+            }))),
+            GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                green,
+                parent: self.clone(),
+                index,
+                start,
+            }),
+        }
+    }
+
+    /// Returns the child nodes in the source order, the tokens are skipped.
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens().filter_map(SyntaxElement::into_node)
+    }
+
+    /// Returns the child tokens in the source order, the nodes are skipped.
+    pub fn child_tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.children_with_tokens().filter_map(SyntaxElement::into_token)
+    }
+
+    /// Returns this node and all nodes in it, in preorder.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        let mut stack = vec![self.clone()];
+        iter::from_fn(move || {
+            let node = stack.pop()?;
+            let children: Vec<_> = node.children().collect();
+            stack.extend(children.into_iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Returns all tokens in this node in the source order, including the
+    /// trivia.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        let mut stack = vec![SyntaxElement::Node(self.clone())];
+        iter::from_fn(move || loop {
+            match stack.pop()? {
+                SyntaxElement::Token(token) => return Some(token),
+                SyntaxElement::Node(node) => {
+                    let children: Vec<_> = node.children_with_tokens().collect();
+                    stack.extend(children.into_iter().rev());
+                }
+            }
+        })
+    }
+
+    /// Returns the token containing `pos`, or the last token if `pos` is the
+    /// end of this node. Returns `None` if `pos` is outside this node.
+    pub fn token_at(&self, pos: Pos) -> Option<SyntaxToken> {
+        let span = self.span();
+        if pos < span.start || pos > span.end {
+            return None;
+        }
+        let mut node = self.clone();
+        loop {
+            let child = node
+                .children_with_tokens()
+                .find(|child| pos < child.span().end)
+                .or_else(|| node.children_with_tokens().last())?;
+            match child {
+                SyntaxElement::Node(child) => node = child,
+                SyntaxElement::Token(token) => return Some(token),
+            }
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &SyntaxNode) -> bool {
+        self.0.start == other.0.start && self.green().ptr_eq(other.green())
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let span = self.span();
+        write!(f, "{:?}@{}..{}", self.kind(), span.start.to_usize(), span.end.to_usize())
+    }
+}
+
+/// A token of the concrete syntax tree, a leaf.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    green: GreenToken,
+    parent: SyntaxNode,
+
+    /// The index of this token in the children of its parent.
+    index: usize,
+    start: Pos,
+}
+
+impl SyntaxToken {
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    #[inline]
+    pub fn span(&self) -> Span {
+        Span::new(self.start, self.start + self.green.len())
+    }
+
+    #[inline]
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// Returns the index of this token in the children of its parent.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the nodes and tokens before this token in its parent, the
+    /// nearest one first.
+    pub fn prev_siblings_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        prev_siblings(Some(self.parent()), self.index, self.start)
+    }
+}
+
+/// Returns the children of `parent` before the one at `index`, which starts
+/// at `start`, the nearest one first. The elements are created lazily, so
+/// looking at the trivia right before a child doesn't walk all siblings.
+fn prev_siblings(
+    parent: Option<SyntaxNode>,
+    mut index: usize,
+    mut start: Pos,
+) -> impl Iterator<Item = SyntaxElement> {
+    iter::from_fn(move || {
+        let parent = parent.as_ref()?;
+        index = index.checked_sub(1)?;
+        let green = parent.green().children()[index].clone();
+        start -= green.len();
+        Some(parent.child(index, green, start))
+    })
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let span = self.span();
+        write!(
+            f, "{:?}@{}..{} {:?}",
+            self.kind(), span.start.to_usize(), span.end.to_usize(), self.text(),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    #[inline]
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    #[inline]
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+
+    /// Returns the nodes and tokens before this element in its parent, the
+    /// nearest one first.
+    pub fn prev_siblings_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        match self {
+            SyntaxElement::Node(node) => prev_siblings(node.parent(), node.0.index, node.0.start),
+            SyntaxElement::Token(token) => {
+                prev_siblings(Some(token.parent()), token.index, token.start)
+            }
+        }
+    }
+
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
+
+impl From<SyntaxNode> for SyntaxElement {
+    fn from(node: SyntaxNode) -> SyntaxElement {
+        SyntaxElement::Node(node)
+    }
+}
+
+impl From<SyntaxToken> for SyntaxElement {
+    fn from(token: SyntaxToken) -> SyntaxElement {
+        SyntaxElement::Token(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::green::GreenNodeBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_navigation() {
+        // `f  x` at position 10.
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(SyntaxKind::Root);
+        builder.start_node(SyntaxKind::CallExpr);
+        builder.start_node(SyntaxKind::NameRef);
+        builder.token(SyntaxKind::Ident, "f");
+        builder.finish_node();
+        builder.token(SyntaxKind::Whitespace, "  ");
+        builder.start_node(SyntaxKind::NameRef);
+        builder.token(SyntaxKind::Ident, "x");
+        builder.finish_node();
+        builder.finish_node();
+        builder.finish_node();
+        let root = SyntaxNode::new_root(builder.finish(), Pos::from_usize(10));
+
+        let span = |start, end| Span::new(Pos::from_usize(start), Pos::from_usize(end));
+        assert_eq!(root.span(), span(10, 14));
+        let kinds = root.descendants().map(|node| node.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds, [
+            SyntaxKind::Root, SyntaxKind::CallExpr, SyntaxKind::NameRef, SyntaxKind::NameRef,
+        ]);
+
+        let x = root.token_at(Pos::from_usize(13)).unwrap();
+        assert_eq!((x.text(), x.span()), ("x", span(13, 14)));
+        assert_eq!(root.token_at(Pos::from_usize(14)), Some(x.clone()));
+        assert_eq!(root.token_at(Pos::from_usize(11)).unwrap().kind(), SyntaxKind::Whitespace);
+        assert_eq!(root.token_at(Pos::from_usize(15)), None);
+
+        let ancestors = x.parent().ancestors().map(|node| node.kind()).collect::<Vec<_>>();
+        assert_eq!(ancestors, [SyntaxKind::NameRef, SyntaxKind::CallExpr, SyntaxKind::Root]);
+        assert_eq!(x.parent().parent().unwrap().text(), "f  x");
+
+        let prev = x.parent().prev_siblings_with_tokens().collect::<Vec<_>>();
+        assert_eq!(prev.iter().map(|element| element.kind()).collect::<Vec<_>>(), [
+            SyntaxKind::Whitespace, SyntaxKind::NameRef,
+        ]);
+        assert_eq!(prev[1].span(), span(10, 11));
+        let space = prev[0].clone().into_token().unwrap();
+        assert_eq!(space.prev_siblings_with_tokens().collect::<Vec<_>>(), &prev[1..]);
+        assert_eq!(x.prev_siblings_with_tokens().count(), 0);
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Type inference of deeply nested `let` chains, e.g.
+//!
+//! ```text
+//! let x0 = fn y => y in
+//! let x1 = fn y => x0 y in
+//! ...
+//! x999 1
+//! ```
+//!
+//! Every `let` generalizes its value with all the previous bindings in scope.
+//! The `let_chain` group infers the whole chain. The `generalize` groups
+//! measure one generalization with `n` bindings in scope, by the levels and
+//! by scanning the environment for the free type variables like the type
+//! checker did before the levels:
+//!
+//! ```text
+//! cargo bench -p kona_type_ir --bench let_chain
+//! ```
+
+use std::{collections::HashSet, io, rc::Rc};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use kona_diagnostic::{DiagnosticsEngine, emitter::HumanEmitter};
+use kona_memory::intern::symbol::Symbol;
+use kona_parse::parse::parse_file;
+use kona_source::source_map::SourceMap;
+use kona_type_ir::{
+    infer::InferContext,
+    ty::{
+        context::{Arenas, TyCtxt},
+        ty::{Ty, TyScheme, TyVar},
+    },
+};
+
+const SIZES: [usize; 4] = [100, 500, 1000, 2000];
+
+fn let_chain(n: usize) -> String {
+    let mut src = String::from("let x0 = fn y => y in\n");
+    for i in 1..n {
+        src.push_str(&format!("let x{} = fn y => x{} y in\n", i, i - 1));
+    }
+    src.push_str(&format!("x{} 1\n", n - 1));
+    src
+}
+
+fn bench_let_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("let_chain");
+    for n in SIZES {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file("bench.kona".to_string(), let_chain(n));
+        let emitter = HumanEmitter::new(Box::new(io::sink()));
+        let diag = DiagnosticsEngine::with_emitter(Rc::new(sm), Box::new(emitter));
+        let expr = parse_file(&file, &diag);
+        assert!(!diag.has_errors());
+
+        group.bench_with_input(BenchmarkId::from_parameter(n), &expr, |b, expr| {
+            b.iter(|| {
+                let arenas = Arenas::default();
+                let tcx = TyCtxt::new(&arenas);
+                InferContext::new(&tcx, &diag).infer_program(expr);
+                assert!(!diag.has_errors());
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_generalize(c: &mut Criterion) {
+    let diag = DiagnosticsEngine::new(Rc::new(SourceMap::new()));
+    let mut levels = c.benchmark_group("generalize/levels");
+    for n in SIZES {
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let mut infcx = InferContext::new(&tcx, &diag);
+        let (env, ty) = let_chain_env(&tcx, n);
+        for (i, scheme) in env.iter().enumerate() {
+            infcx.define(Symbol::intern(&format!("x{}", i)), *scheme);
+        }
+        let by_env_scan = generalize_by_env_scan(&tcx, &env, ty);
+        assert_eq!(quantified(infcx.generalize(ty)), quantified(by_env_scan));
+
+        levels.bench_function(BenchmarkId::from_parameter(n), |b| b.iter(|| infcx.generalize(ty)));
+    }
+    levels.finish();
+
+    let mut env_scan = c.benchmark_group("generalize/env_scan");
+    for n in SIZES {
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let (env, ty) = let_chain_env(&tcx, n);
+        env_scan.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| generalize_by_env_scan(&tcx, &env, ty))
+        });
+    }
+    env_scan.finish();
+}
+
+/// Returns the environment of the `n`-th `let` in a chain, the polymorphic
+/// functions before it and a parameter `y: 'y` in scope, and the type of its
+/// value `'a -> 'y` to generalize, where only `'a` is generalizable.
+fn let_chain_env<'tcx>(
+    tcx: &TyCtxt<'tcx>,
+    n: usize,
+) -> (Vec<TyScheme<'tcx>>, &'tcx Ty<'tcx>) {
+    let mut env: Vec<_> = (0..n)
+        .map(|_| {
+            let var = tcx.mk_ty_var(1);
+            let quantified = tcx.alloc_ty_vars(var.ty_vars());
+            TyScheme::Poly(quantified, tcx.mk_fn(var, var))
+        })
+        .collect();
+    let param = tcx.mk_ty_var(0);
+    env.push(TyScheme::Mono(param));
+    (env, tcx.mk_fn(tcx.mk_ty_var(1), param))
+}
+
+/// Generalizes the type variables in `ty` that are not free in `env`, found
+/// by walking the types of all bindings.
+fn generalize_by_env_scan<'tcx>(
+    tcx: &TyCtxt<'tcx>,
+    env: &[TyScheme<'tcx>],
+    ty: &'tcx Ty<'tcx>,
+) -> TyScheme<'tcx> {
+    let mut free = HashSet::new();
+    for scheme in env {
+        let quantified = quantified(*scheme);
+        free.extend(
+            scheme.ty().ty_vars().iter().map(|var| var.id).filter(|id| !quantified.contains(id)),
+        );
+    }
+    let vars: Vec<&'tcx TyVar<'tcx>> =
+        ty.ty_vars().into_iter().filter(|var| !free.contains(&var.id)).collect();
+
+    if vars.is_empty() {
+        TyScheme::Mono(ty)
+    } else {
+        TyScheme::Poly(tcx.alloc_ty_vars(vars), ty)
+    }
+}
+
+fn quantified(scheme: TyScheme) -> Vec<u32> {
+    match scheme {
+        TyScheme::Mono(_) => Vec::new(),
+        TyScheme::Poly(vars, _) => vars.iter().map(|var| var.id).collect(),
+    }
+}
+
+criterion_group!(benches, bench_let_chain, bench_generalize);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use kona_diagnostic::Diagnostic;
+use kona_memory::intern::symbol::Symbol;
+use kona_source::span::Span;
+
+use crate::ty::{context::TyCtxt, print::TyPrinter, ty::Ty};
+
+/// Why two types can't be unified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnifyError {
+    /// Two different type constructors.
+    Mismatch,
+
+    /// A type variable occurs in the type it is unified with, e.g. `'a` and
+    /// `'a -> int`.
+    InfiniteType,
+
+    /// A comparable type variable is unified with a type containing a function
+    /// type, e.g. `''a` and `int -> int`.
+    NotComparable,
+}
+
+/// Where an expected type comes from, used to explain type errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin {
+    /// The argument of a call is expected to have the parameter type of the
+    /// function. `name` is the name of the function if it is a variable or an
+    /// operator.
+    FnArg { func: Span, name: Option<Symbol> },
+
+    /// The callee of a call is expected to be a function.
+    Callee,
+
+    /// The condition of `if` is expected to be `bool`, `keyword` is the span
+    /// of the `if` keyword.
+    IfCond { keyword: Span },
+
+    /// The `else` branch is expected to have the type of the `then` branch.
+    IfBranches { then: Span },
+}
+
+/// A type error, the type of an expression (`found`) is not the `expected`
+/// one.
+pub struct TypeError<'tcx> {
+    pub kind: UnifyError,
+
+    /// The span of the expression which has the `found` type.
+    pub span: Span,
+    pub expected: &'tcx Ty<'tcx>,
+    pub found: &'tcx Ty<'tcx>,
+    pub origin: Origin,
+}
+
+impl<'tcx> TypeError<'tcx> {
+    /// Creates the diagnostic of this error. The types are printed as they
+    /// are now, so this should be called right after the unification fails.
+    pub fn to_diagnostic(&self, tcx: &TyCtxt<'tcx>) -> Diagnostic {
+        if self.kind == UnifyError::InfiniteType {
+            return Diagnostic::error("cannot construct an infinite type")
+                .with_primary(self.span, "the type of this would contain itself");
+        }
+
+        if self.kind == UnifyError::NotComparable {
+            return self.not_comparable_diagnostic(tcx);
+        }
+
+        // Share the names of type variables between the two types.
+        let mut printer = TyPrinter::new();
+        let expected = printer.print_ty(tcx.resolve(self.expected));
+        let found = printer.print_ty(tcx.resolve(self.found));
+        let mismatch = format!("expected `{}`, found `{}`", expected, found);
+        match self.origin {
+            Origin::FnArg { func, name } => {
+                let label = match name {
+                    Some(name) => format!("the parameter of `{}` is `{}`", name, expected),
+                    None => format!("the parameter of this function is `{}`", expected),
+                };
+                Diagnostic::error("mismatched types")
+                    .with_primary(self.span, mismatch)
+                    .with_secondary(func, label)
+            }
+            Origin::Callee => {
+                Diagnostic::error(format!("expected function, found `{}`", found))
+                    .with_primary(self.span, "call expression requires a function")
+            }
+            Origin::IfCond { keyword } => {
+                Diagnostic::error("mismatched types")
+                    .with_primary(self.span, mismatch)
+                    .with_secondary(keyword, "`if` conditions must be `bool`")
+            }
+            Origin::IfBranches { then } => {
+                Diagnostic::error("`if` and `else` have incompatible types")
+                    .with_primary(self.span, mismatch)
+                    .with_secondary(then, "expected because of this")
+            }
+        }
+    }
+
+    /// Creates the diagnostic of a [`UnifyError::NotComparable`]. The type
+    /// variables are bound even if the types are not comparable, so the
+    /// expected and found types are the same by now.
+    fn not_comparable_diagnostic(&self, tcx: &TyCtxt<'tcx>) -> Diagnostic {
+        let found = tcx.resolve(self.found);
+        let diagnostic = Diagnostic::error("functions cannot be compared")
+            .with_primary(self.span, format!("`{}` is not comparable", found));
+        let diagnostic = match self.origin {
+            Origin::FnArg { func, name } => {
+                let label = match name {
+                    Some(name) => format!("the parameter of `{}` must be comparable", name),
+                    None => "the parameter of this function must be comparable".to_string(),
+                };
+                diagnostic.with_secondary(func, label)
+            }
+            Origin::Callee | Origin::IfCond { .. } | Origin::IfBranches { .. } => diagnostic,
+        };
+        diagnostic.with_note(
+            "a type variable written `''a` only stands for types without functions",
+        )
+    }
+}
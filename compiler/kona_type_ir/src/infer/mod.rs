@@ -1,6 +1,669 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-pub struct InferContext {
+//! Hindley-Milner type inference.
+//!
+//! The inference is in the style of Algorithm J: every unknown type is a
+//! [`TyVar`], and unification binds type variables in place instead of
+//! composing substitutions. The AST is elaborated into the typed IR along the
+//! way, and the types of all typed expressions are resolved at the end.
 
+use std::collections::HashMap;
+
+use kona_ast::{decl as ast_decl, expr::{self as ast, lit::LitKind}};
+use kona_diagnostic::{Diagnostic, DiagnosticsEngine};
+use kona_memory::intern::symbol::Symbol;
+use kona_source::span::Span;
+
+use crate::{
+    tir::{decl::Decl, expr::{Expr, ExprKind}},
+    ty::{context::TyCtxt, ty::{Ty, TyScheme, TyVar}},
+};
+
+use self::error::{Origin, TypeError, UnifyError};
+
+pub mod error;
+
+/// The typing environment, maps variables to their type schemes.
+///
+/// Bindings are pushed and popped like a stack, a later binding shadows the
+/// earlier ones with the same name.
+#[derive(Default)]
+pub struct TyEnv<'tcx> {
+    bindings: Vec<(Symbol, TyScheme<'tcx>)>,
+}
+
+impl<'tcx> TyEnv<'tcx> {
+    pub fn new() -> TyEnv<'tcx> {
+        TyEnv::default()
+    }
+
+    pub fn lookup(&self, name: Symbol) -> Option<TyScheme<'tcx>> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, scheme)| *scheme)
+    }
+
+    pub fn push(&mut self, name: Symbol, scheme: TyScheme<'tcx>) {
+        self.bindings.push((name, scheme));
+    }
+
+    pub fn pop(&mut self) {
+        self.bindings.pop();
+    }
+}
+
+pub struct InferContext<'a, 'tcx> {
+    tcx: &'a TyCtxt<'tcx>,
+    diag: &'a DiagnosticsEngine,
+    env: TyEnv<'tcx>,
+
+    /// The current level, the number of enclosing `let` values. See
+    /// [`TyVar::level`].
+    level: u32,
+}
+
+impl<'a, 'tcx> InferContext<'a, 'tcx> {
+    pub fn new(tcx: &'a TyCtxt<'tcx>, diag: &'a DiagnosticsEngine) -> InferContext<'a, 'tcx> {
+        InferContext { tcx, diag, env: TyEnv::new(), level: 0 }
+    }
+
+    /// Creates a fresh type variable at the current level.
+    pub fn new_ty_var(&self) -> &'tcx Ty<'tcx> {
+        self.tcx.mk_ty_var(self.level)
+    }
+
+    /// Defines a variable visible to the whole program, e.g. a built-in
+    /// function.
+    pub fn define(&mut self, name: Symbol, scheme: TyScheme<'tcx>) {
+        self.env.push(name, scheme);
+    }
+
+    /// Infers the types of a program, and elaborates it into the typed IR.
+    ///
+    /// All type errors are reported to `diag`. A typed program is returned
+    /// even if there are errors, the types of the erroneous parts are unknown
+    /// type variables.
+    pub fn infer_program(&mut self, expr: &ast::Expr) -> &'tcx Expr<'tcx> {
+        let expr = self.infer_expr(expr);
+        let expr = self.tcx.alloc_expr(expr);
+        self.resolve_expr(expr);
+        expr
+    }
+
+    /// Infers the type of a top-level declaration. Errors are reported like
+    /// [`InferContext::infer_program`].
+    ///
+    /// The variable is not defined yet, call [`InferContext::define`] with the
+    /// scheme of the declaration to make it visible to the rest of the
+    /// program, e.g. after checking that there are no errors.
+    pub fn infer_decl(&mut self, decl: &ast_decl::Decl) -> Decl<'tcx> {
+        self.level += 1;
+        let value = self.infer_expr(&decl.value);
+        self.level -= 1;
+        let value = self.tcx.alloc_expr(value);
+        self.resolve_expr(value);
+        let scheme = self.generalize(value.ty());
+        Decl { name: decl.name, value, scheme, span: decl.span }
+    }
+
+    fn infer_expr(&mut self, expr: &ast::Expr) -> Expr<'tcx> {
+        let tcx = self.tcx;
+        let span = expr.span;
+        match &expr.kind {
+            ast::ExprKind::Error => Expr::new(ExprKind::Error, self.new_ty_var(), span),
+            ast::ExprKind::Unit => Expr::new(ExprKind::Unit, tcx.types.unit, span),
+            ast::ExprKind::Lit(lit) => {
+                let ty = match lit.kind {
+                    LitKind::Int => tcx.types.int,
+                    LitKind::Float => tcx.types.float,
+                    LitKind::String => tcx.types.string,
+                    LitKind::Char => tcx.types.char,
+                    LitKind::Bool => tcx.types.bool,
+                };
+                Expr::new(ExprKind::Lit(*lit), ty, span)
+            }
+            ast::ExprKind::Ident(ident) => match self.env.lookup(ident.name) {
+                Some(scheme) => {
+                    let ty = self.instantiate(scheme);
+                    Expr::new(ExprKind::Var(*ident), ty, span)
+                }
+                None => {
+                    self.report_unbound(ident.name, span);
+                    Expr::new(ExprKind::Error, self.new_ty_var(), span)
+                }
+            },
+            ast::ExprKind::PendingSeq(..) => {
+                unreachable!("infix expressions must be resolved before type inference")
+            }
+            ast::ExprKind::InfixOp(op, lhs, rhs) => {
+                let op = ast::ident::Ident::new(op.name, op.span);
+                let op_ty = match self.env.lookup(op.name) {
+                    Some(scheme) => self.instantiate(scheme),
+                    None => {
+                        self.report_unbound(op.name, op.span);
+                        self.new_ty_var()
+                    }
+                };
+                let lhs = self.infer_expr(lhs);
+                let rhs = self.infer_expr(rhs);
+                let func = Origin::FnArg { func: op.span, name: Some(op.name) };
+                let partial_ty = self.infer_call(op_ty, op.span, func, &lhs);
+                let ty = self.infer_call(partial_ty, op.span, func, &rhs);
+                let kind = ExprKind::InfixCall(
+                    op, tcx.alloc_expr(lhs), tcx.alloc_expr(rhs),
+                );
+                Expr::new(kind, ty, span)
+            }
+
+            // Fixity declarations and parentheses only matter to the parser.
+            ast::ExprKind::Infix(_, _, body) => self.infer_expr(body),
+            ast::ExprKind::Paren(inner) => self.infer_expr(inner),
+
+            ast::ExprKind::Fn(param, body) => {
+                let param_ty = self.new_ty_var();
+                self.env.push(param.name, TyScheme::Mono(param_ty));
+                let body = self.infer_expr(body);
+                self.env.pop();
+                let ty = tcx.mk_fn(param_ty, body.ty());
+                Expr::new(ExprKind::Lambda(*param, tcx.alloc_expr(body)), ty, span)
+            }
+            ast::ExprKind::FnCall(func, args) => {
+                let mut call = self.infer_expr(func);
+                for arg in args {
+                    let arg = self.infer_expr(arg);
+                    let name = match &call.kind {
+                        ExprKind::Var(ident) => Some(ident.name),
+                        _ => None,
+                    };
+                    let origin = Origin::FnArg { func: call.span, name };
+                    let ty = self.infer_call(call.ty(), call.span, origin, &arg);
+                    let span = Span::new(call.span.start, arg.span.end);
+                    let kind = ExprKind::Call(tcx.alloc_expr(call), tcx.alloc_expr(arg));
+                    call = Expr::new(kind, ty, span);
+                }
+                call
+            }
+            ast::ExprKind::If(cond, then, else_) => {
+                let cond = self.infer_expr(cond);
+                let keyword = Span::new(span.start, span.start + "if".len());
+                self.expect(&cond, tcx.types.bool, Origin::IfCond { keyword });
+                let then = self.infer_expr(then);
+                let else_ = self.infer_expr(else_);
+                self.expect(&else_, then.ty(), Origin::IfBranches { then: then.span });
+                let ty = then.ty();
+                let kind = ExprKind::If(
+                    tcx.alloc_expr(cond), tcx.alloc_expr(then), tcx.alloc_expr(else_),
+                );
+                Expr::new(kind, ty, span)
+            }
+            ast::ExprKind::Block(items) => {
+                let items: Vec<Expr<'tcx>> =
+                    items.iter().map(|item| self.infer_expr(item)).collect();
+                let ty = items.last().map_or(tcx.types.unit, |item| item.ty());
+                Expr::new(ExprKind::Block(tcx.alloc_exprs(items)), ty, span)
+            }
+            ast::ExprKind::Let(name, value, body) => {
+                self.level += 1;
+                let value = self.infer_expr(value);
+                self.level -= 1;
+                let scheme = self.generalize(value.ty());
+                self.env.push(name.name, scheme);
+                let body = self.infer_expr(body);
+                self.env.pop();
+                let ty = body.ty();
+                let kind = ExprKind::Let(
+                    *name, tcx.alloc_expr(value), tcx.alloc_expr(body),
+                );
+                Expr::new(kind, ty, span)
+            }
+        }
+    }
+
+    /// Infers the result type of applying a function of type `func_ty` to
+    /// `arg`, `origin` explains the parameter type of the function.
+    fn infer_call(
+        &mut self,
+        func_ty: &'tcx Ty<'tcx>,
+        func_span: Span,
+        origin: Origin,
+        arg: &Expr<'tcx>,
+    ) -> &'tcx Ty<'tcx> {
+        match func_ty.fn_sig() {
+            Some((param_ty, ret_ty)) => {
+                self.expect(arg, param_ty, origin);
+                ret_ty
+            }
+            None => {
+                let ret_ty = self.new_ty_var();
+                let expected = self.tcx.mk_fn(arg.ty(), ret_ty);
+                self.unify_or_report(TypeError {
+                    kind: UnifyError::Mismatch,
+                    span: func_span,
+                    expected,
+                    found: func_ty,
+                    origin: Origin::Callee,
+                });
+                ret_ty
+            }
+        }
+    }
+
+    /// Instantiates the quantified type variables of a type scheme with fresh
+    /// type variables, which are comparable if the quantified ones are.
+    pub fn instantiate(&self, scheme: TyScheme<'tcx>) -> &'tcx Ty<'tcx> {
+        match scheme {
+            TyScheme::Mono(ty) => ty,
+            TyScheme::Poly(vars, ty) => {
+                let subst: HashMap<u32, &'tcx Ty<'tcx>> = vars
+                    .iter()
+                    .map(|var| {
+                        let fresh = if var.comparable.get() {
+                            self.tcx.mk_comparable_ty_var(self.level)
+                        } else {
+                            self.new_ty_var()
+                        };
+                        (var.id, fresh)
+                    })
+                    .collect();
+                self.substitute(ty, &subst)
+            }
+        }
+    }
+
+    fn substitute(
+        &self,
+        ty: &'tcx Ty<'tcx>,
+        subst: &HashMap<u32, &'tcx Ty<'tcx>>,
+    ) -> &'tcx Ty<'tcx> {
+        match ty.shallow_resolve() {
+            ty @ Ty::Var(var) => subst.get(&var.id).copied().unwrap_or(ty),
+            ty @ Ty::Con(_, []) => ty,
+            Ty::Con(con, args) => self.tcx.mk_con(
+                *con,
+                args.iter().map(|arg| *self.substitute(arg, subst)),
+            ),
+        }
+    }
+
+    /// Generalizes the type variables in `ty` that are not free in the
+    /// environment, i.e. the ones at a deeper level than the current one.
+    pub fn generalize(&self, ty: &'tcx Ty<'tcx>) -> TyScheme<'tcx> {
+        let vars: Vec<&'tcx TyVar<'tcx>> = ty
+            .ty_vars()
+            .into_iter()
+            .filter(|var| var.level.get() > self.level)
+            .collect();
+
+        if vars.is_empty() {
+            TyScheme::Mono(ty)
+        } else {
+            TyScheme::Poly(self.tcx.alloc_ty_vars(vars), ty)
+        }
+    }
+
+    /// Unifies two types, binds the type variables in them if necessary.
+    ///
+    /// Unification is not transactional, the type variables bound before an
+    /// error stay bound.
+    pub fn unify(&self, a: &'tcx Ty<'tcx>, b: &'tcx Ty<'tcx>) -> Result<(), UnifyError> {
+        match (a.shallow_resolve(), b.shallow_resolve()) {
+            (Ty::Var(a), Ty::Var(b)) if a.id == b.id => Ok(()),
+            (Ty::Var(var), ty) | (ty, Ty::Var(var)) => self.bind(var, ty),
+            (Ty::Con(a_con, a_args), Ty::Con(b_con, b_args)) => {
+                if a_con != b_con {
+                    return Err(UnifyError::Mismatch);
+                }
+                a_args
+                    .iter()
+                    .zip(b_args.iter())
+                    .try_for_each(|(a, b)| self.unify(a, b))
+            }
+        }
+    }
+
+    fn bind(&self, var: &'tcx TyVar<'tcx>, ty: &'tcx Ty<'tcx>) -> Result<(), UnifyError> {
+        occurs_check_and_adjust_levels(var, ty)?;
+
+        // The type variable is bound even if the type is not comparable, so
+        // that the other uses of it don't report the same error again.
+        let comparable = if var.comparable.get() { make_comparable(ty) } else { Ok(()) };
+        var.data.set(Some(ty));
+        comparable
+    }
+
+    /// Unifies the type of `expr` with the `expected` type, reports an error
+    /// if they don't match.
+    fn expect(&self, expr: &Expr<'tcx>, expected: &'tcx Ty<'tcx>, origin: Origin) {
+        self.unify_or_report(TypeError {
+            kind: UnifyError::Mismatch,
+            span: expr.span,
+            expected,
+            found: expr.ty(),
+            origin,
+        });
+    }
+
+    /// Unifies the expected and found type of `error`, reports it with the
+    /// actual [`UnifyError`] if they don't match.
+    fn unify_or_report(&self, error: TypeError<'tcx>) {
+        if let Err(kind) = self.unify(error.expected, error.found) {
+            let error = TypeError { kind, ..error };
+            self.diag.emit(error.to_diagnostic(self.tcx));
+        }
+    }
+
+    fn report_unbound(&self, name: Symbol, span: Span) {
+        self.diag.emit(
+            Diagnostic::error(format!("cannot find `{}` in this scope", name))
+                .with_primary(span, "not found in this scope"),
+        );
+    }
+
+    /// Replaces the types of `expr` and all its sub-expressions with their
+    /// resolved types.
+    fn resolve_expr(&self, expr: &Expr<'tcx>) {
+        expr.ty.set(self.tcx.resolve(expr.ty()));
+        match &expr.kind {
+            ExprKind::Error
+            | ExprKind::Unit
+            | ExprKind::Lit(_)
+            | ExprKind::Var(_) => (),
+            ExprKind::Lambda(_, body) => self.resolve_expr(body),
+            ExprKind::Call(func, arg) => {
+                self.resolve_expr(func);
+                self.resolve_expr(arg);
+            }
+            ExprKind::InfixCall(_, lhs, rhs) => {
+                self.resolve_expr(lhs);
+                self.resolve_expr(rhs);
+            }
+            ExprKind::Let(_, value, body) => {
+                self.resolve_expr(value);
+                self.resolve_expr(body);
+            }
+            ExprKind::If(cond, then, else_) => {
+                self.resolve_expr(cond);
+                self.resolve_expr(then);
+                self.resolve_expr(else_);
+            }
+            ExprKind::Block(items) => {
+                items.iter().for_each(|item| self.resolve_expr(item));
+            }
+        }
+    }
+}
+
+/// Checks that `var` doesn't occur in `ty`, and lowers the levels of the type
+/// variables in `ty` to the level of `var`.
+fn occurs_check_and_adjust_levels<'tcx>(
+    var: &'tcx TyVar<'tcx>,
+    ty: &'tcx Ty<'tcx>,
+) -> Result<(), UnifyError> {
+    match ty.shallow_resolve() {
+        Ty::Var(other) if other.id == var.id => Err(UnifyError::InfiniteType),
+        Ty::Var(other) => {
+            if other.level.get() > var.level.get() {
+                other.level.set(var.level.get());
+            }
+            Ok(())
+        }
+        Ty::Con(_, args) => args
+            .iter()
+            .try_for_each(|arg| occurs_check_and_adjust_levels(var, arg)),
+    }
+}
+
+/// Makes the type variables in `ty` comparable, fails if `ty` contains a
+/// function type. See [`TyVar::comparable`].
+fn make_comparable<'tcx>(ty: &'tcx Ty<'tcx>) -> Result<(), UnifyError> {
+    match ty.shallow_resolve() {
+        Ty::Var(var) => {
+            var.comparable.set(true);
+            Ok(())
+        }
+        Ty::Con(con, _) if con.is_fn() => Err(UnifyError::NotComparable),
+        Ty::Con(_, args) => args.iter().try_for_each(make_comparable),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use kona_diagnostic::emitter::{Emitter, HumanEmitter};
+    use kona_parse::parse::{parse_decls, parse_file};
+    use kona_source::source_map::SourceMap;
+
+    use crate::ty::context::Arenas;
+
+    use super::*;
+
+    struct NoEmitter;
+
+    impl Emitter for NoEmitter {
+        fn emit(&mut self, _: &Diagnostic, _: &SourceMap) {}
+    }
+
+    /// Infers the type of `src`, returns the resolved type and the error
+    /// messages.
+    fn infer(src: &str) -> (String, Vec<String>) {
+        let (ty, diagnostics, _) = infer_with_diagnostics(src);
+        let errors = diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        (ty, errors)
+    }
+
+    fn infer_with_diagnostics(src: &str) -> (String, Vec<Diagnostic>, Rc<SourceMap>) {
+        let sm = SourceMap::new();
+        let file = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let sm = Rc::new(sm);
+        let diag = DiagnosticsEngine::with_emitter(sm.clone(), Box::new(NoEmitter));
+        let expr = parse_file(&file, &diag);
+        assert!(!diag.has_errors(), "syntax error in {:?}", src);
+
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let mut infcx = InferContext::new(&tcx, &diag);
+        let int_op = tcx.mk_fn(tcx.types.int, tcx.mk_fn(tcx.types.int, tcx.types.int));
+        infcx.define(Symbol::intern("+"), TyScheme::Mono(int_op));
+        let a = tcx.mk_comparable_ty_var(1);
+        let eq_op = infcx.generalize(tcx.mk_fn(a, tcx.mk_fn(a, tcx.types.bool)));
+        infcx.define(Symbol::intern("="), eq_op);
+
+        let expr = infcx.infer_program(&expr);
+        (expr.ty().to_string(), diag.take_diagnostics(), sm)
+    }
+
+    fn assert_rendered_err(src: &str, expected: &[&str]) {
+        let (_, diagnostics, sm) = infer_with_diagnostics(src);
+        assert_eq!(diagnostics.len(), 1, "wrong errors in {:?}", src);
+        let rendered = HumanEmitter::render(&diagnostics[0], &sm);
+        let expected = expected.join("\n") + "\n\n";
+        assert_eq!(rendered, expected, "wrong error in {:?}", src);
+    }
+
+    fn assert_ty(src: &str, expected: &str) {
+        let (ty, errors) = infer(src);
+        assert!(errors.is_empty(), "unexpected errors in {:?}: {:?}", src, errors);
+        assert_eq!(ty, expected, "wrong type of {:?}", src);
+    }
+
+    fn assert_err(src: &str, expected: &str) {
+        let (_, errors) = infer(src);
+        assert_eq!(errors, [expected], "wrong errors in {:?}", src);
+    }
+
+    #[test]
+    fn test_infer_simple_exprs() {
+        assert_ty("1", "int");
+        assert_ty("\"hello\"", "string");
+        assert_ty("()", "unit");
+        assert_ty("1 + 2", "int");
+        assert_ty("(true; 1.5)", "float");
+        assert_ty("if true then 1 else 2", "int");
+        assert_ty("fn x => x + 1", "int -> int");
+        assert_ty("(fn x => x) 1", "int");
+        assert_ty("fn f => fn x => f (f x)", "('a -> 'a) -> 'a -> 'a");
+    }
+
+    #[test]
+    fn test_infer_let_polymorphism() {
+        assert_ty("let id = fn x => x in (id 1; id true)", "bool");
+        assert_ty("let id = fn x => x in id id 1", "int");
+        assert_ty(
+            "let const = fn x => fn y => x in const 1 \"a\"",
+            "int",
+        );
+
+        // Lambda bound variables are monomorphic.
+        assert_err("(fn id => (id 1; id true)) (fn x => x)", "mismatched types");
+
+        // The type variable of `x` is free in the environment, `y` must not be
+        // generalized.
+        assert_err(
+            "fn x => let y = x in (y + 1; y true)",
+            "expected function, found `int`",
+        );
+
+        // `y` becomes reachable from `x` in the environment after `x y`, its
+        // level is lowered so it isn't generalized either.
+        assert_err(
+            "fn x => let f = fn y => (x y; y) in (f 1; f true)",
+            "mismatched types",
+        );
+        assert_ty(
+            "fn x => let f = fn y => (x; y) in (f 1; f true)",
+            "'a -> bool",
+        );
+    }
+
+    #[test]
+    fn test_infer_comparable() {
+        assert_ty("fn x => fn y => x = y", "''a -> ''a -> bool");
+        assert_ty("let eq = fn x => fn y => x = y in (eq 1 2; eq () ())", "bool");
+        assert_ty("fn f => fn x => f x = 1", "('a -> int) -> 'a -> bool");
+
+        assert_err("let f = fn x => x in f = f", "functions cannot be compared");
+        assert_err(
+            "let eq = fn x => fn y => x = y in eq (fn x => x)",
+            "functions cannot be compared",
+        );
+
+        // `f` is comparable after `f = f`, it can't be called.
+        assert_err("fn f => (f = f; f 1)", "functions cannot be compared");
+    }
+
+    #[test]
+    fn test_infer_decls() {
+        let sm = SourceMap::new();
+        let src = "let id = fn x => x\nlet n = id 1 + 1\nlet b = id true";
+        let file = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let diag = DiagnosticsEngine::with_emitter(Rc::new(sm), Box::new(NoEmitter));
+        let decls = parse_decls(&file, &diag);
+
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let mut infcx = InferContext::new(&tcx, &diag);
+        let int_op = tcx.mk_fn(tcx.types.int, tcx.mk_fn(tcx.types.int, tcx.types.int));
+        infcx.define(Symbol::intern("+"), TyScheme::Mono(int_op));
+
+        // Declared variables are generalized like `let` bound ones.
+        let schemes: Vec<String> = decls
+            .iter()
+            .map(|decl| {
+                let decl = infcx.infer_decl(decl);
+                infcx.define(decl.name.name, decl.scheme);
+                decl.scheme.to_string()
+            })
+            .collect();
+        assert!(!diag.has_errors());
+        assert_eq!(schemes, ["'a -> 'a", "int", "bool"]);
+    }
+
+    #[test]
+    fn test_infer_errors() {
+        assert_err("if 1 then 2 else 3", "mismatched types");
+        assert_err(
+            "if true then 2 else \"3\"",
+            "`if` and `else` have incompatible types",
+        );
+        assert_err("1 2", "expected function, found `int`");
+        assert_err("fn x => x x", "cannot construct an infinite type");
+        assert_err("x + 1", "cannot find `x` in this scope");
+
+        // The unknown type of an erroneous expression doesn't cause more errors.
+        assert_err("(x 1) + 1", "cannot find `x` in this scope");
+    }
+
+    #[test]
+    fn test_type_error_messages() {
+        assert_rendered_err("let f = fn x => x + 1 in f \"a\"", &[
+            "error: mismatched types",
+            " --> test.kona:1:28",
+            "  |",
+            "1 | let f = fn x => x + 1 in f \"a\"",
+            "  |                          - ^^^ expected `int`, found `string`",
+            "  |                          |",
+            "  |                          the parameter of `f` is `int`",
+        ]);
+        assert_rendered_err("true + 1", &[
+            "error: mismatched types",
+            " --> test.kona:1:1",
+            "  |",
+            "1 | true + 1",
+            "  | ^^^^ - the parameter of `+` is `int`",
+            "  | |",
+            "  | expected `int`, found `bool`",
+        ]);
+        assert_rendered_err("(fn f => f 1) (fn x => x x)", &[
+            "error: cannot construct an infinite type",
+            " --> test.kona:1:24",
+            "  |",
+            "1 | (fn f => f 1) (fn x => x x)",
+            "  |                        ^ the type of this would contain itself",
+        ]);
+        assert_rendered_err("if 1 then 2 else 3", &[
+            "error: mismatched types",
+            " --> test.kona:1:4",
+            "  |",
+            "1 | if 1 then 2 else 3",
+            "  | -- ^ expected `bool`, found `int`",
+            "  | |",
+            "  | `if` conditions must be `bool`",
+        ]);
+        assert_rendered_err("fn f => if true then f 1 + 1 else \"a\"", &[
+            "error: `if` and `else` have incompatible types",
+            " --> test.kona:1:35",
+            "  |",
+            "1 | fn f => if true then f 1 + 1 else \"a\"",
+            "  |                      -------      ^^^ expected `int`, found `string`",
+            "  |                      |",
+            "  |                      expected because of this",
+        ]);
+        assert_rendered_err("fn f => (f 1; f) + 1", &[
+            "error: mismatched types",
+            " --> test.kona:1:9",
+            "  |",
+            "1 | fn f => (f 1; f) + 1",
+            "  |         ^^^^^^^^ - the parameter of `+` is `int`",
+            "  |         |",
+            "  |         expected `int`, found `int -> 'a`",
+        ]);
+        assert_rendered_err("let f = fn x => x in f = f", &[
+            "error: functions cannot be compared",
+            " --> test.kona:1:22",
+            "  |",
+            "1 | let f = fn x => x in f = f",
+            "  |                      ^ - the parameter of `=` must be comparable",
+            "  |                      |",
+            "  |                      `'a -> 'a` is not comparable",
+            "  |",
+            "  = note: a type variable written `''a` only stands for types without functions",
+        ]);
+    }
 }
@@ -0,0 +1,20 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use kona_ast::expr::ident::Ident;
+use kona_source::span::Span;
+
+use crate::ty::ty::TyScheme;
+
+use super::expr::Expr;
+
+/// A typed top-level declaration `let x = e`.
+pub struct Decl<'tir> {
+    pub name: Ident,
+    pub value: &'tir Expr<'tir>,
+
+    /// The generalized type of the value, the type of `name` in the
+    /// declarations after this one.
+    pub scheme: TyScheme<'tir>,
+    pub span: Span,
+}
@@ -0,0 +1,146 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+//! Dumps the typed IR for debugging, e.g. in `kona check --emit=tir`.
+
+use std::fmt::Write;
+
+use crate::ty::print::TyPrinter;
+
+use super::expr::{Expr, ExprKind};
+
+/// Dumps a typed expression like [`kona_ast::dump::dump_expr`], with the type
+/// of every node. The type variables are named consistently in the whole dump.
+///
+/// ```text
+/// Let id : int
+///   Lambda x : 'a -> 'a
+///     Var x : 'a
+///   Call : int
+///     Var id : int -> int
+///     Lit 1 : int
+/// ```
+pub fn dump_expr(expr: &Expr) -> String {
+    let mut out = String::new();
+    write_expr(&mut out, &mut TyPrinter::new(), expr, 0);
+    out
+}
+
+/// Dumps the types of all `let` bound variables in source order, and the type
+/// of the whole expression at the end.
+///
+/// ```text
+/// val id : 'a -> 'a
+/// - : int
+/// ```
+pub fn dump_types(expr: &Expr) -> String {
+    fn walk(out: &mut String, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Error | ExprKind::Unit | ExprKind::Lit(_) | ExprKind::Var(_) => (),
+            ExprKind::Lambda(_, body) => walk(out, body),
+            ExprKind::Call(lhs, rhs) | ExprKind::InfixCall(_, lhs, rhs) => {
+                walk(out, lhs);
+                walk(out, rhs);
+            }
+            ExprKind::Let(name, value, body) => {
+                // Each variable is printed with a fresh printer, like a type
+                // scheme.
+                writeln!(out, "val {} : {}", name.name, value.ty()).unwrap();
+                walk(out, value);
+                walk(out, body);
+            }
+            ExprKind::If(cond, then, else_) => {
+                walk(out, cond);
+                walk(out, then);
+                walk(out, else_);
+            }
+            ExprKind::Block(items) => items.iter().for_each(|item| walk(out, item)),
+        }
+    }
+
+    let mut out = String::new();
+    walk(&mut out, expr);
+    writeln!(out, "- : {}", expr.ty()).unwrap();
+    out
+}
+
+fn write_expr(out: &mut String, printer: &mut TyPrinter, expr: &Expr, depth: usize) {
+    let mut children = Vec::new();
+    let node = match &expr.kind {
+        ExprKind::Error => "Error".to_string(),
+        ExprKind::Unit => "Unit".to_string(),
+        ExprKind::Lit(lit) => format!("Lit {}", lit.symbol),
+        ExprKind::Var(ident) => format!("Var {}", ident.name),
+        ExprKind::Lambda(param, body) => {
+            children.push(*body);
+            format!("Lambda {}", param.name)
+        }
+        ExprKind::Call(func, arg) => {
+            children.extend([*func, *arg]);
+            "Call".to_string()
+        }
+        ExprKind::InfixCall(op, lhs, rhs) => {
+            children.extend([*lhs, *rhs]);
+            format!("InfixCall {}", op.name)
+        }
+        ExprKind::Let(name, value, body) => {
+            children.extend([*value, *body]);
+            format!("Let {}", name.name)
+        }
+        ExprKind::If(cond, then, else_) => {
+            children.extend([*cond, *then, *else_]);
+            "If".to_string()
+        }
+        ExprKind::Block(items) => {
+            children.extend(items.iter());
+            "Block".to_string()
+        }
+    };
+
+    let ty = printer.print_ty(expr.ty());
+    writeln!(out, "{:indent$}{} : {}", "", node, ty, indent = depth * 2).unwrap();
+    for child in children {
+        write_expr(out, printer, child, depth + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use kona_diagnostic::DiagnosticsEngine;
+    use kona_parse::parse::parse_file;
+    use kona_source::source_map::SourceMap;
+
+    use crate::{infer::InferContext, ty::context::{Arenas, TyCtxt}};
+
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let sm = SourceMap::new();
+        let src = "let id = fn x => x in let one = id 1 in (id; one)";
+        let file = sm.load_virtual_file("test.kona".to_string(), src.to_string());
+        let diag = DiagnosticsEngine::new(Rc::new(sm));
+        let expr = parse_file(&file, &diag);
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let expr = InferContext::new(&tcx, &diag).infer_program(&expr);
+        assert!(!diag.has_errors());
+
+        assert_eq!(dump_expr(expr), [
+            "Let id : int",
+            "  Lambda x : 'a -> 'a",
+            "    Var x : 'a",
+            "  Let one : int",
+            "    Call : int",
+            "      Var id : int -> int",
+            "      Lit 1 : int",
+            "    Block : int",
+            "      Var id : 'b -> 'b",
+            "      Var one : int",
+            "",
+        ].join("\n"));
+        assert_eq!(dump_types(expr), "val id : 'a -> 'a\nval one : int\n- : int\n");
+    }
+}
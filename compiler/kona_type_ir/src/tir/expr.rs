@@ -1,24 +1,56 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use kona_ast::expr::ident::Ident;
+use std::cell::Cell;
+
+use kona_ast::expr::{ident::Ident, lit::Lit};
 use kona_source::span::Span;
 
+use crate::ty::ty::Ty;
+
+/// A typed expression.
 pub struct Expr<'tir> {
     pub kind: ExprKind<'tir>,
+
+    /// The type of the expression. It may contain bound type variables during
+    /// type inference, and is replaced with the resolved type at the end.
+    pub ty: Cell<&'tir Ty<'tir>>,
     pub span: Span,
 }
 
+impl<'tir> Expr<'tir> {
+    #[inline]
+    pub fn new(kind: ExprKind<'tir>, ty: &'tir Ty<'tir>, span: Span) -> Expr<'tir> {
+        Expr { kind, ty: Cell::new(ty), span }
+    }
+
+    #[inline]
+    pub fn ty(&self) -> &'tir Ty<'tir> {
+        self.ty.get()
+    }
+}
+
 pub enum ExprKind<'tir> {
+    /// An erroneous expression, the error has already been reported. It has
+    /// a fresh type variable as its type, so that it doesn't cause more type
+    /// errors.
+    Error,
+
     // Zero-arity tuple, remove this when we have tuples.
     Unit,
-    Lit(/* Lit */),
-    Lambda(Vec<Ident>, &'tir Expr<'tir>),
+    Lit(Lit),
+    Var(Ident),
+    Lambda(Ident, &'tir Expr<'tir>),
 
+    /// A function application with one argument, `f a b` is `Call(Call(f, a),
+    /// b)`.
     Call(&'tir Expr<'tir>, &'tir Expr<'tir>),
     InfixCall(Ident, &'tir Expr<'tir>, &'tir Expr<'tir>),
 
     Let(Ident, &'tir Expr<'tir>, &'tir Expr<'tir>),
     If(&'tir Expr<'tir>, &'tir Expr<'tir>, &'tir Expr<'tir>),
-    Block(&'tir Expr<'tir>),
+
+    /// A sequence of expressions, the value of the last one is the value of the
+    /// block.
+    Block(&'tir [Expr<'tir>]),
 }
@@ -1,4 +1,6 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
+pub mod decl;
+pub mod dump;
 pub mod expr;
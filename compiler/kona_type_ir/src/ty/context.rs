@@ -0,0 +1,167 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::cell::Cell;
+
+use kona_memory::{
+    arena::{dropless_arena::DroplessArena, typed_arena::TypedArena},
+    intern::symbol::{sym, Symbol},
+};
+
+use crate::tir::expr::Expr;
+
+use super::ty::{Ty, TyCon, TyVar};
+
+/// The arenas of types and typed expressions.
+///
+/// Everything in the typed IR refers to each other by `&'tcx` references into
+/// these arenas, the arenas must outlive the [`TyCtxt`]. The types are `Copy`
+/// and share a [`DroplessArena`], the type variables and the expressions hold
+/// `Cell`s and have their own arenas:
+///
+/// ```
+/// use kona_type_ir::ty::context::{Arenas, TyCtxt};
+///
+/// let arenas = Arenas::default();
+/// let tcx = TyCtxt::new(&arenas);
+/// let ty = tcx.mk_fn(tcx.types.int, tcx.types.bool);
+/// assert!(ty.fn_sig().is_some());
+/// ```
+#[derive(Default)]
+pub struct Arenas<'tcx> {
+    dropless: DroplessArena,
+    ty_var: TypedArena<TyVar<'tcx>>,
+    expr: TypedArena<Expr<'tcx>>,
+}
+
+/// The type context, creates and owns (via the [`Arenas`]) all types.
+pub struct TyCtxt<'tcx> {
+    arenas: &'tcx Arenas<'tcx>,
+
+    /// The id of the next type variable.
+    next_ty_var_id: Cell<u32>,
+
+    /// The built-in types.
+    pub types: CommonTypes<'tcx>,
+}
+
+/// The built-in nullary types, allocated once in the [`TyCtxt`].
+pub struct CommonTypes<'tcx> {
+    pub unit: &'tcx Ty<'tcx>,
+    pub bool: &'tcx Ty<'tcx>,
+    pub int: &'tcx Ty<'tcx>,
+    pub float: &'tcx Ty<'tcx>,
+    pub char: &'tcx Ty<'tcx>,
+    pub string: &'tcx Ty<'tcx>,
+}
+
+impl<'tcx> TyCtxt<'tcx> {
+    pub fn new(arenas: &'tcx Arenas<'tcx>) -> TyCtxt<'tcx> {
+        let mk = |name: Symbol| &*arenas.dropless.alloc(Ty::Con(TyCon::new(name, 0), &[]));
+        let types = CommonTypes {
+            unit: mk(sym::unit),
+            bool: mk(sym::bool),
+            int: mk(sym::int),
+            float: mk(sym::float),
+            char: mk(sym::char),
+            string: mk(sym::string),
+        };
+        TyCtxt { arenas, next_ty_var_id: Cell::new(0), types }
+    }
+
+    pub fn mk_ty(&self, ty: Ty<'tcx>) -> &'tcx Ty<'tcx> {
+        self.arenas.dropless.alloc(ty)
+    }
+
+    /// Creates an application of type constructor `con`, the number of `args`
+    /// must be the arity of `con`.
+    pub fn mk_con<I>(&self, con: TyCon, args: I) -> &'tcx Ty<'tcx>
+    where
+        I: IntoIterator<Item = Ty<'tcx>>,
+    {
+        // Collect the arguments first, the iterator may allocate types too.
+        let args: Vec<Ty<'tcx>> = args.into_iter().collect();
+        let args: &'tcx [Ty<'tcx>] = self.arenas.dropless.alloc_slice(args);
+        debug_assert_eq!(args.len(), con.arity, "wrong number of type arguments");
+        self.mk_ty(Ty::Con(con, args))
+    }
+
+    /// Creates a function type `param -> ret`.
+    pub fn mk_fn(&self, param: &'tcx Ty<'tcx>, ret: &'tcx Ty<'tcx>) -> &'tcx Ty<'tcx> {
+        self.mk_con(TyCon::fn_con(), [*param, *ret])
+    }
+
+    /// Creates a fresh unbound type variable at the given level, see
+    /// [`TyVar::level`].
+    pub fn mk_ty_var(&self, level: u32) -> &'tcx Ty<'tcx> {
+        let var = TyVar::new(self.next_ty_var_id(), level);
+        self.mk_ty(Ty::Var(self.arenas.ty_var.alloc(var)))
+    }
+
+    /// Creates a fresh unbound type variable like [`TyCtxt::mk_ty_var`], which
+    /// can only stand for a comparable type, see [`TyVar::comparable`].
+    pub fn mk_comparable_ty_var(&self, level: u32) -> &'tcx Ty<'tcx> {
+        let var = TyVar::new(self.next_ty_var_id(), level);
+        var.comparable.set(true);
+        self.mk_ty(Ty::Var(self.arenas.ty_var.alloc(var)))
+    }
+
+    /// Allocates the quantified type variables of a [`TyScheme::Poly`], they
+    /// are copies of the given unbound type variables.
+    ///
+    /// [`TyScheme::Poly`]: super::ty::TyScheme::Poly
+    pub fn alloc_ty_vars<I>(&self, vars: I) -> &'tcx [TyVar<'tcx>]
+    where
+        I: IntoIterator<Item = &'tcx TyVar<'tcx>>,
+    {
+        let vars: Vec<TyVar<'tcx>> = vars
+            .into_iter()
+            .map(|var| {
+                let copy = TyVar::new(var.id, var.level.get());
+                copy.comparable.set(var.comparable.get());
+                copy
+            })
+            .collect();
+        self.arenas.ty_var.alloc_slice(vars)
+    }
+
+    pub fn alloc_expr(&self, expr: Expr<'tcx>) -> &'tcx Expr<'tcx> {
+        self.arenas.expr.alloc(expr)
+    }
+
+    pub fn alloc_exprs<I>(&self, exprs: I) -> &'tcx [Expr<'tcx>]
+    where
+        I: IntoIterator<Item = Expr<'tcx>>,
+    {
+        let exprs: Vec<Expr<'tcx>> = exprs.into_iter().collect();
+        self.arenas.expr.alloc_slice(exprs)
+    }
+
+    fn next_ty_var_id(&self) -> u32 {
+        let id = self.next_ty_var_id.get();
+        self.next_ty_var_id.set(id + 1);
+        id
+    }
+
+    /// Resolves all bound type variables in `ty` deeply, the result only
+    /// contains unbound type variables.
+    pub fn resolve(&self, ty: &'tcx Ty<'tcx>) -> &'tcx Ty<'tcx> {
+        match ty.shallow_resolve() {
+            ty @ Ty::Var(_) => ty,
+            ty @ Ty::Con(_, []) => ty,
+            ty @ Ty::Con(con, args) => {
+                let resolved: Vec<&'tcx Ty<'tcx>> =
+                    args.iter().map(|arg| self.resolve(arg)).collect();
+                let unchanged = args
+                    .iter()
+                    .zip(&resolved)
+                    .all(|(arg, resolved)| std::ptr::eq(arg, *resolved));
+                if unchanged {
+                    ty
+                } else {
+                    self.mk_con(*con, resolved.into_iter().copied())
+                }
+            }
+        }
+    }
+}
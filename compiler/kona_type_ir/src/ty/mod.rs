@@ -1,4 +1,7 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
+#[allow(clippy::module_inception)]
 pub mod ty;
+pub mod context;
+pub mod print;
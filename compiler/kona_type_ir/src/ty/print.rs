@@ -0,0 +1,178 @@
+// Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
+// root for license information.
+
+use std::fmt::{self, Write};
+
+use super::ty::{Ty, TyScheme};
+
+/// Prints types in the SML style, e.g. `'a -> 'a`, `(int -> int) -> int` and
+/// `int list`.
+///
+/// Type variables are named `'a`, `'b`, ..., `'z`, `'a1`, `'b1`, ... in the
+/// order of their first appearance, the comparable ones with two quotes, e.g.
+/// `''a`. The names are shared by all types printed by the same printer, so
+/// that types in one message can refer to the same variables, e.g. "expected
+/// `'a -> 'b`, found `'b`".
+///
+/// The [`Display`](fmt::Display) implementations of [`Ty`] and [`TyScheme`]
+/// print with a fresh printer.
+#[derive(Default)]
+pub struct TyPrinter {
+    /// The ids of the named type variables, the index is the name.
+    names: Vec<u32>,
+}
+
+impl TyPrinter {
+    pub fn new() -> TyPrinter {
+        TyPrinter::default()
+    }
+
+    pub fn print_ty(&mut self, ty: &Ty) -> String {
+        let mut out = String::new();
+        self.write_ty(&mut out, ty, false);
+        out
+    }
+
+    /// Prints a type scheme, the quantifiers are implicit like in SML, e.g.
+    /// `forall 'a. 'a -> 'a` is printed as `'a -> 'a`.
+    pub fn print_scheme(&mut self, scheme: &TyScheme) -> String {
+        self.print_ty(scheme.ty())
+    }
+
+    /// Writes `ty` to `out`. Function types are parenthesized if `nested`, i.e.
+    /// on the left of an arrow or as an argument of another type constructor.
+    fn write_ty(&mut self, out: &mut String, ty: &Ty, nested: bool) {
+        match ty {
+            Ty::Var(var) => match var.data.get() {
+                Some(ty) => self.write_ty(out, ty, nested),
+                None => {
+                    let index = self.name_index(var.id);
+                    if var.comparable.get() {
+                        out.push('\'');
+                    }
+                    write_var_name(out, index);
+                }
+            },
+            Ty::Con(con, args) if con.is_fn() => {
+                if nested {
+                    out.push('(');
+                }
+                self.write_ty(out, &args[0], true);
+                out.push_str(" -> ");
+                self.write_ty(out, &args[1], false);
+                if nested {
+                    out.push(')');
+                }
+            }
+            Ty::Con(con, []) => write!(out, "{}", con.name).unwrap(),
+            Ty::Con(con, [arg]) => {
+                self.write_ty(out, arg, true);
+                write!(out, " {}", con.name).unwrap();
+            }
+            Ty::Con(con, args) => {
+                out.push('(');
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.write_ty(out, arg, false);
+                }
+                write!(out, ") {}", con.name).unwrap();
+            }
+        }
+    }
+
+    fn name_index(&mut self, id: u32) -> usize {
+        match self.names.iter().position(|&named| named == id) {
+            Some(index) => index,
+            None => {
+                self.names.push(id);
+                self.names.len() - 1
+            }
+        }
+    }
+}
+
+/// Writes the `index`-th type variable name, `'a` to `'z`, then `'a1` to
+/// `'z1`, and so on.
+fn write_var_name(out: &mut String, index: usize) {
+    out.push('\'');
+    out.push((b'a' + (index % 26) as u8) as char);
+    if index >= 26 {
+        write!(out, "{}", index / 26).unwrap();
+    }
+}
+
+impl fmt::Display for Ty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&TyPrinter::new().print_ty(self))
+    }
+}
+
+impl fmt::Display for TyScheme<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&TyPrinter::new().print_scheme(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kona_memory::intern::symbol::Symbol;
+
+    use crate::ty::{context::{Arenas, TyCtxt}, ty::TyCon};
+
+    use super::*;
+
+    #[test]
+    fn test_print_ty() {
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let (int, string) = (tcx.types.int, tcx.types.string);
+        let list = TyCon::new(Symbol::intern("list"), 1);
+        let map = TyCon::new(Symbol::intern("map"), 2);
+        let a = tcx.mk_ty_var(0);
+        let b = tcx.mk_ty_var(0);
+
+        assert_eq!(int.to_string(), "int");
+        assert_eq!(tcx.mk_fn(int, tcx.mk_fn(int, int)).to_string(), "int -> int -> int");
+        assert_eq!(tcx.mk_fn(tcx.mk_fn(int, int), int).to_string(), "(int -> int) -> int");
+        assert_eq!(tcx.mk_con(list, [*int]).to_string(), "int list");
+        assert_eq!(
+            tcx.mk_con(list, [*tcx.mk_con(list, [*tcx.mk_fn(int, int)])]).to_string(),
+            "(int -> int) list list",
+        );
+        assert_eq!(
+            tcx.mk_con(map, [*string, *tcx.mk_con(list, [*int])]).to_string(),
+            "(string, int list) map",
+        );
+
+        // Variables are named in the order of their first appearance.
+        assert_eq!(tcx.mk_fn(b, tcx.mk_fn(a, b)).to_string(), "'a -> 'b -> 'a");
+
+        // A bound variable is printed as its type.
+        let c = tcx.mk_ty_var(0);
+        if let Ty::Var(var) = c {
+            var.data.set(Some(tcx.mk_fn(a, int)));
+        }
+        assert_eq!(tcx.mk_fn(c, a).to_string(), "('a -> int) -> 'a");
+        assert_eq!(tcx.mk_fn(tcx.mk_comparable_ty_var(0), a).to_string(), "''a -> 'b");
+    }
+
+    #[test]
+    fn test_shared_names() {
+        let arenas = Arenas::default();
+        let tcx = TyCtxt::new(&arenas);
+        let a = tcx.mk_ty_var(0);
+        let b = tcx.mk_ty_var(0);
+
+        let mut printer = TyPrinter::new();
+        assert_eq!(printer.print_ty(tcx.mk_fn(a, b)), "'a -> 'b");
+        assert_eq!(printer.print_ty(b), "'b");
+
+        let mut out = String::new();
+        write_var_name(&mut out, 25);
+        write_var_name(&mut out, 26);
+        write_var_name(&mut out, 53);
+        assert_eq!(out, "'z'a1'b2");
+    }
+}
@@ -1,15 +1,55 @@
 // Copyright (c) Kk Shinkai. All Rights Reserved. See LICENSE.txt in the project
 // root for license information.
 
-use std::cell::Cell;
+use std::{cell::Cell, fmt};
 
-use kona_memory::intern::symbol::Symbol;
+use kona_memory::intern::symbol::{sym, Symbol};
 
+/// A type variable, a placeholder for an unknown type during type inference.
+///
+/// Unifying a type variable with a type binds the type to `data`. A bound type
+/// variable is just an alias of its type, see [`Ty::shallow_resolve`].
 pub struct TyVar<'tcx> {
     pub id: u32,
     pub data: Cell<Option<&'tcx Ty<'tcx>>>,
+
+    /// The `let` nesting depth where the type variable is introduced, used
+    /// for generalization (Rémy's levels).
+    ///
+    /// The type inference enters a deeper level when inferring the value of a
+    /// `let`. When an unbound type variable is unified with a type, the levels
+    /// of type variables in the type are lowered to its level, since they are
+    /// now reachable from where it is introduced. After the value is inferred,
+    /// the type variables still at a deeper level are not reachable from the
+    /// environment, and can be generalized.
+    pub level: Cell<u32>,
+
+    /// Whether the type variable can only stand for a comparable type, i.e. a
+    /// type without functions in it. It is written `''a` like an equality type
+    /// variable of SML, e.g. the type of `=` is `''a -> ''a -> bool`.
+    ///
+    /// Unifying a comparable type variable with a type makes the type variables
+    /// in the type comparable, and fails if the type contains a function type.
+    pub comparable: Cell<bool>,
+}
+
+impl<'tcx> TyVar<'tcx> {
+    /// Creates an unbound type variable at the given level.
+    pub fn new(id: u32, level: u32) -> TyVar<'tcx> {
+        TyVar {
+            id,
+            data: Cell::new(None),
+            level: Cell::new(level),
+            comparable: Cell::new(false),
+        }
+    }
+
+    pub fn is_bound(&self) -> bool {
+        self.data.get().is_some()
+    }
 }
 
+#[derive(Clone, Copy)]
 pub enum Ty<'tcx> {
     /// A type variable.
     Var(&'tcx TyVar<'tcx>),
@@ -18,12 +58,108 @@ pub enum Ty<'tcx> {
     Con(TyCon, &'tcx [Ty<'tcx>]),
 }
 
+impl<'tcx> Ty<'tcx> {
+    /// Follows the bound type variables until it reaches an unbound type
+    /// variable or a type constructor. The arguments of type constructors are
+    /// not resolved.
+    ///
+    /// The bound type variables along the way are rebound to the result, so
+    /// that we don't have to walk through the long chain again.
+    pub fn shallow_resolve(&'tcx self) -> &'tcx Ty<'tcx> {
+        match self {
+            Ty::Var(var) => match var.data.get() {
+                Some(ty) => {
+                    let resolved = ty.shallow_resolve();
+                    var.data.set(Some(resolved));
+                    resolved
+                }
+                None => self,
+            },
+            Ty::Con(..) => self,
+        }
+    }
+
+    /// Returns the unbound type variables in this type, in the order of their
+    /// first appearance.
+    pub fn ty_vars(&'tcx self) -> Vec<&'tcx TyVar<'tcx>> {
+        fn walk<'tcx>(ty: &'tcx Ty<'tcx>, vars: &mut Vec<&'tcx TyVar<'tcx>>) {
+            match ty.shallow_resolve() {
+                Ty::Var(var) => {
+                    if !vars.iter().any(|v| v.id == var.id) {
+                        vars.push(var);
+                    }
+                }
+                Ty::Con(_, args) => args.iter().for_each(|arg| walk(arg, vars)),
+            }
+        }
+
+        let mut vars = Vec::new();
+        walk(self, &mut vars);
+        vars
+    }
+
+    /// Returns the parameter and return type, if this is a function type.
+    pub fn fn_sig(&'tcx self) -> Option<(&'tcx Ty<'tcx>, &'tcx Ty<'tcx>)> {
+        match self.shallow_resolve() {
+            Ty::Con(con, args) if con.is_fn() => Some((&args[0], &args[1])),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for Ty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ty::Var(var) => match var.data.get() {
+                Some(ty) => write!(f, "{:?}", ty),
+                None => write!(f, "?{}", var.id),
+            },
+            Ty::Con(con, args) if con.is_fn() => {
+                write!(f, "({:?} -> {:?})", args[0], args[1])
+            }
+            Ty::Con(con, []) => write!(f, "{}", con.name),
+            Ty::Con(con, args) => write!(f, "{}{:?}", con.name, args),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct TyCon {
     pub name: Symbol,
     pub arity: usize,
 }
 
+impl TyCon {
+    pub fn new(name: Symbol, arity: usize) -> TyCon {
+        TyCon { name, arity }
+    }
+
+    /// Returns the function type constructor `->`.
+    pub fn fn_con() -> TyCon {
+        TyCon::new(sym::arrow, 2)
+    }
+
+    pub fn is_fn(&self) -> bool {
+        self.arity == 2 && self.name == sym::arrow
+    }
+}
+
+/// A type scheme, the type of a `let` bound variable.
+///
+/// A polymorphic type scheme `forall 'a. 'a -> 'a` quantifies type variables,
+/// every use of the variable instantiates them with fresh type variables.
+/// The quantified type variables are identified by their `id`s.
+#[derive(Clone, Copy)]
 pub enum TyScheme<'tcx> {
     Mono(&'tcx Ty<'tcx>),
     Poly(&'tcx [TyVar<'tcx>], &'tcx Ty<'tcx>),
 }
+
+impl<'tcx> TyScheme<'tcx> {
+    /// Returns the type without the quantifiers.
+    pub fn ty(&self) -> &'tcx Ty<'tcx> {
+        match self {
+            TyScheme::Mono(ty) | TyScheme::Poly(_, ty) => ty,
+        }
+    }
+}